@@ -0,0 +1,222 @@
+//! Benchmarks of `align_with` over the tasks, modes, sequence lengths, error rates, alphabets, and `k`,
+//! and of `align_banded` over the band width.
+//!
+//! The sequences are generated by a fixed-seed generator, so the numbers are comparable between runs.
+//! Run by `cargo bench`, or `cargo bench -- tasks` to run a single group.
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use edlib_sys::{align_banded, align_with, AlignConfig, AlignMode, AlignTask};
+use std::hint::black_box;
+
+const DNA: &[u8] = b"ACGT";
+const PROTEIN: &[u8] = b"ACDEFGHIKLMNPQRSTVWY";
+
+// A xorshift64* generator, to avoid depending on `rand`.
+struct Rng(u64);
+
+impl Rng {
+    fn new(seed: u64) -> Self {
+        Self(seed.max(1))
+    }
+    fn next(&mut self) -> u64 {
+        self.0 ^= self.0 >> 12;
+        self.0 ^= self.0 << 25;
+        self.0 ^= self.0 >> 27;
+        self.0.wrapping_mul(0x2545_F491_4F6C_DD1D)
+    }
+    fn below(&mut self, n: usize) -> usize {
+        (self.next() % n as u64) as usize
+    }
+    fn base(&mut self, alphabet: &[u8]) -> u8 {
+        alphabet[self.below(alphabet.len())]
+    }
+}
+
+// Return a random `target` over the `alphabet` of length `len`, and a `query` with
+// `error_rate` substitutions, insertions, and deletions in the same proportion.
+fn pair(len: usize, error_rate: f64, alphabet: &[u8]) -> (Vec<u8>, Vec<u8>) {
+    let mut rng = Rng::new(len as u64 ^ (error_rate * 1e6) as u64 ^ alphabet.len() as u64);
+    let target: Vec<u8> = (0..len).map(|_| rng.base(alphabet)).collect();
+    let mut query = Vec::with_capacity(len);
+    for &base in target.iter() {
+        if (rng.next() as f64 / u64::MAX as f64) < error_rate {
+            match rng.below(3) {
+                0 => query.push(rng.base(alphabet)),
+                1 => query.extend([base, rng.base(alphabet)].iter()),
+                _ => {}
+            }
+        } else {
+            query.push(base);
+        }
+    }
+    (query, target)
+}
+
+fn bytes() -> Vec<u8> {
+    (0..=255).collect()
+}
+
+fn tasks(c: &mut Criterion) {
+    let mut group = c.benchmark_group("tasks");
+    group.sample_size(10);
+    for len in [100, 1_000, 10_000] {
+        let (query, target) = pair(len, 0.05, DNA);
+        group.throughput(Throughput::Elements((query.len() * target.len()) as u64));
+        for task in [
+            AlignTask::Distance,
+            AlignTask::Location,
+            AlignTask::Alignment,
+        ] {
+            let config = AlignConfig::new(AlignMode::Global, task);
+            let id = BenchmarkId::new(format!("{:?}", task), len);
+            group.bench_with_input(id, &config, |b, config| {
+                b.iter(|| align_with(black_box(&query), black_box(&target), config))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn modes(c: &mut Criterion) {
+    let mut group = c.benchmark_group("modes");
+    group.sample_size(10);
+    let (query, target) = pair(1_000, 0.05, DNA);
+    // Embed the query in a longer target so that the semi-global modes have something to skip.
+    let mut rng = Rng::new(42);
+    let flank: Vec<u8> = (0..1_000).map(|_| rng.base(DNA)).collect();
+    let target = [flank.as_slice(), &target, &flank].concat();
+    for mode in [
+        AlignMode::Global,
+        AlignMode::Prefix,
+        AlignMode::Infix,
+        AlignMode::Suffix,
+        AlignMode::Overlap,
+        AlignMode::Local,
+    ] {
+        for task in [AlignTask::Distance, AlignTask::Alignment] {
+            let config = AlignConfig::new(mode, task);
+            let id = BenchmarkId::new(format!("{:?}", mode), format!("{:?}", task));
+            group.bench_with_input(id, &config, |b, config| {
+                b.iter(|| align_with(black_box(&query), black_box(&target), config))
+            });
+        }
+    }
+    group.finish();
+}
+
+fn error_rates(c: &mut Criterion) {
+    let mut group = c.benchmark_group("error_rates");
+    group.sample_size(10);
+    for error_rate in [0.01, 0.05, 0.15, 0.3] {
+        let (query, target) = pair(10_000, error_rate, DNA);
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Alignment);
+        group.bench_with_input(
+            BenchmarkId::from_parameter(error_rate),
+            &config,
+            |b, config| b.iter(|| align_with(black_box(&query), black_box(&target), config)),
+        );
+    }
+    group.finish();
+}
+
+fn alphabets(c: &mut Criterion) {
+    let mut group = c.benchmark_group("alphabets");
+    let bytes = bytes();
+    for (name, alphabet) in [
+        ("dna", DNA),
+        ("protein", PROTEIN),
+        ("bytes", bytes.as_slice()),
+    ] {
+        let (query, target) = pair(1_000, 0.05, alphabet);
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment);
+        group.bench_with_input(BenchmarkId::from_parameter(name), &config, |b, config| {
+            b.iter(|| align_with(black_box(&query), black_box(&target), config))
+        });
+    }
+    group.finish();
+}
+
+// The cost of `k` below, around, and above the actual distance.
+fn thresholds(c: &mut Criterion) {
+    let mut group = c.benchmark_group("k");
+    group.sample_size(10);
+    let (query, target) = pair(10_000, 0.05, DNA);
+    let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance);
+    let dist = align_with(&query, &target, &config).dist();
+    let ks = [
+        ("none", None),
+        ("tenth", Some(dist / 10)),
+        ("dist", Some(dist)),
+        ("double", Some(dist * 2)),
+    ];
+    for (name, k) in ks.iter() {
+        let config = match k {
+            Some(k) => config.clone().with_k(*k),
+            None => config.clone(),
+        };
+        group.bench_with_input(BenchmarkId::from_parameter(name), &config, |b, config| {
+            b.iter(|| align_with(black_box(&query), black_box(&target), config))
+        });
+    }
+    group.finish();
+}
+
+// `obtainAlignment` switches to Hirschberg's algorithm when the traceback data exceeds the memory budget
+// (1 MB by default, i.e., around 1,800 bases for two sequences of the same length).
+fn hirschberg(c: &mut Criterion) {
+    let mut group = c.benchmark_group("hirschberg");
+    group.sample_size(10);
+    for len in [1_000, 1_500, 2_000, 3_000] {
+        let (query, target) = pair(len, 0.05, DNA);
+        group.throughput(Throughput::Elements((query.len() * target.len()) as u64));
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment);
+        group.bench_with_input(BenchmarkId::from_parameter(len), &config, |b, config| {
+            b.iter(|| align_with(black_box(&query), black_box(&target), config))
+        });
+    }
+    // The same problem under different memory budgets.
+    let (query, target) = pair(10_000, 0.05, DNA);
+    group.throughput(Throughput::Elements((query.len() * target.len()) as u64));
+    for (name, bytes) in [("1KB", 1 << 10), ("1MB", 1 << 20), ("64MB", 1 << 26)] {
+        let config =
+            AlignConfig::new(AlignMode::Global, AlignTask::Alignment).with_memory_budget(bytes);
+        group.bench_with_input(BenchmarkId::new("budget", name), &config, |b, config| {
+            b.iter(|| align_with(black_box(&query), black_box(&target), config))
+        });
+    }
+    group.finish();
+}
+
+// `align_banded` over the band width, against the global mode growing `k` by doubling.
+fn banded(c: &mut Criterion) {
+    let mut group = c.benchmark_group("banded");
+    group.sample_size(10);
+    let (query, target) = pair(10_000, 0.05, DNA);
+    group.throughput(Throughput::Elements((query.len() * target.len()) as u64));
+    let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment);
+    group.bench_with_input(
+        BenchmarkId::from_parameter("global"),
+        &config,
+        |b, config| b.iter(|| align_with(black_box(&query), black_box(&target), config)),
+    );
+    // The band should contain the end of the sequences.
+    let diff = target.len().abs_diff(query.len());
+    for band in [64, 256, 1024] {
+        let band = band.max(diff);
+        group.bench_with_input(BenchmarkId::from_parameter(band), &band, |b, &band| {
+            b.iter(|| align_banded(black_box(&query), black_box(&target), band, 0))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(
+    benches,
+    tasks,
+    modes,
+    error_rates,
+    alphabets,
+    thresholds,
+    hirschberg,
+    banded
+);
+criterion_main!(benches);
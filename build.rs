@@ -3,6 +3,8 @@
 
 fn main() {
     println!("{:?}", cc::Build::new().get_compiler().path());
+    // cc emits `rerun-if-env-changed`, which disables the default rerun on any change of the package.
+    println!("cargo:rerun-if-changed=src/edlib");
     if cfg!(target_os = "linux") {
         cc::Build::new()
             .cpp(true)
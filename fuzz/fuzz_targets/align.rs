@@ -0,0 +1,104 @@
+//! Fuzz `align_with` over all the modes, tasks, equalities, and `k`, checking the results
+//! copied from edlib's raw pointers.
+//!
+//! Run by `cargo +nightly fuzz run align` in the root directory of the crate.
+#![no_main]
+use edlib_sys::edit::validate;
+use edlib_sys::{align_with, AlignConfig, AlignMode, AlignTask, CigarFormat};
+use libfuzzer_sys::arbitrary::{self, Arbitrary};
+use libfuzzer_sys::fuzz_target;
+
+const MODES: [AlignMode; 6] = [
+    AlignMode::Global,
+    AlignMode::Prefix,
+    AlignMode::Infix,
+    AlignMode::Suffix,
+    AlignMode::Overlap,
+    AlignMode::Local,
+];
+
+// Sequences are truncated so that each input runs quickly.
+const MAX_LEN: usize = 1_000;
+
+#[derive(Debug, Arbitrary)]
+struct Input {
+    mode: u8,
+    k: Option<u8>,
+    memory_budget: Option<u16>,
+    equalities: Vec<(u8, u8)>,
+    query: Vec<u8>,
+    target: Vec<u8>,
+}
+
+// Count the bases in a CIGAR string for each operation symbol.
+fn count(cigar: &str, symbols: &str) -> usize {
+    let mut total = 0;
+    let mut length = 0;
+    for c in cigar.chars() {
+        match c.to_digit(10) {
+            Some(d) => length = 10 * length + d as usize,
+            None => {
+                if symbols.contains(c) {
+                    total += length;
+                }
+                length = 0;
+            }
+        }
+    }
+    total
+}
+
+fuzz_target!(|input: Input| {
+    let mode = MODES[input.mode as usize % MODES.len()];
+    let query = &input.query[..input.query.len().min(MAX_LEN)];
+    let target = &input.target[..input.target.len().min(MAX_LEN)];
+    let equalities = &input.equalities[..input.equalities.len().min(16)];
+    let config = |task| {
+        let mut config = AlignConfig::new(mode, task).with_equalities(equalities);
+        if let Some(bytes) = input.memory_budget {
+            config = config.with_memory_budget(bytes as usize);
+        }
+        match input.k {
+            Some(k) => config.with_k(k as u32),
+            None => config,
+        }
+    };
+    let dist = align_with(query, target, &config(AlignTask::Distance));
+    // Only the overlap and local modes may find nothing without `k`, if the sequences do not overlap
+    // or share no base at all.
+    if input.k.is_none() && !matches!(mode, AlignMode::Overlap | AlignMode::Local) {
+        assert!(dist.is_found());
+    }
+    if let Some(k) = input.k.filter(|_| dist.is_found()) {
+        assert!(dist.dist() <= k as u32);
+    }
+    let location = align_with(query, target, &config(AlignTask::Location));
+    assert_eq!(location.dist(), dist.dist());
+    if let Some((starts, ends)) = location.locations() {
+        assert_eq!(starts.len(), ends.len());
+        for (&start, &end) in starts.iter().zip(ends.iter()) {
+            assert!(0 <= start && start <= end + 1 && end < target.len() as i32);
+        }
+    }
+    let aln = align_with(query, target, &config(AlignTask::Alignment));
+    assert_eq!(aln.dist(), dist.dist());
+    if !aln.is_found() {
+        assert!(aln.operations().is_none());
+        return;
+    }
+    // The path consumes exactly the aligned parts of both sequences, with `dist()` edits.
+    if let Err(why) = validate(query, target, &aln, equalities) {
+        panic!("{}: {:?}", why, aln);
+    }
+    let query_len = aln
+        .query_location()
+        .map_or(0, |(s, e)| e.wrapping_add(1) - s);
+    // The ends are `-1 as usize` if nothing is aligned.
+    let target_len = aln.location().map_or(0, |(s, e)| e.wrapping_add(1) - s);
+    let standard = aln.cigar(CigarFormat::Standard).unwrap();
+    assert_eq!(count(&standard, "MI"), query_len);
+    assert_eq!(count(&standard, "MD"), target_len);
+    let extended = aln.cigar(CigarFormat::Extended).unwrap();
+    assert_eq!(count(&extended, "XID"), aln.dist() as usize);
+    assert_eq!(count(&extended, "=X"), count(&standard, "M"));
+});
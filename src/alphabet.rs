@@ -0,0 +1,182 @@
+//! # Alphabets
+//!
+//! Edlib builds the alphabet from whatever bytes appear in the sequences, so a lowercase base, a space,
+//! or a stop codon `*` is silently aligned as yet another character.
+//! An [`Alphabet`] checks the sequences before the alignment:
+//!
+//! - [`Alphabet::validate`] rejects any byte out of the alphabet.
+//! - [`Alphabet::normalize`] first folds lowercase letters to uppercase, and converts `U` to `T`
+//!   (or `T` to `U`) if the alphabet has only one of them, then rejects the remaining bytes.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::alphabet::Alphabet;
+//! use edlib_sys::{align, AlignMode, AlignTask};
+//! let dna = Alphabet::dna();
+//! let query = dna.normalize(b"acgu").unwrap();
+//! let target = dna.normalize(b"TTACGTTT").unwrap();
+//! let aln = align(&query, &target, AlignMode::Infix, AlignTask::Location);
+//! assert_eq!(aln.dist(), 0);
+//! let err = dna.normalize(b"ACG T").unwrap_err();
+//! assert_eq!((err.position(), err.symbol()), (3, b' '));
+//! ```
+
+/// A byte out of the alphabet.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InvalidSymbol {
+    position: usize,
+    symbol: u8,
+}
+
+impl InvalidSymbol {
+    /// Return the position of the byte in the sequence.
+    pub fn position(&self) -> usize {
+        self.position
+    }
+    pub fn symbol(&self) -> u8 {
+        self.symbol
+    }
+}
+
+impl std::fmt::Display for InvalidSymbol {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "invalid symbol '{}' at {}",
+            std::ascii::escape_default(self.symbol),
+            self.position
+        )
+    }
+}
+
+impl std::error::Error for InvalidSymbol {}
+
+/// A set of the bytes allowed in the sequences.
+#[derive(Clone, PartialEq, Eq)]
+pub struct Alphabet {
+    members: [bool; 256],
+}
+
+impl std::fmt::Debug for Alphabet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let symbols = self.symbols();
+        f.debug_tuple("Alphabet")
+            .field(&String::from_utf8_lossy(&symbols))
+            .finish()
+    }
+}
+
+impl Alphabet {
+    /// Create an alphabet of the `symbols`. Normalization folds a lowercase letter only if
+    /// its uppercase is in the alphabet and itself is not.
+    pub fn custom(symbols: &[u8]) -> Self {
+        let mut members = [false; 256];
+        for &c in symbols {
+            members[c as usize] = true;
+        }
+        Self { members }
+    }
+    /// `ACGT`.
+    pub fn dna() -> Self {
+        Self::custom(b"ACGT")
+    }
+    /// `ACGU`.
+    pub fn rna() -> Self {
+        Self::custom(b"ACGU")
+    }
+    /// The IUPAC nucleotide codes: `ACGTU` and the ambiguity codes `RYSWKMBDHVN`.
+    /// Note that edlib treats an ambiguity code as a distinct character unless the equalities are given
+    /// by [`crate::AlignConfig::with_equalities`].
+    pub fn iupac() -> Self {
+        Self::custom(b"ACGTURYSWKMBDHVN")
+    }
+    /// The 20 standard amino acids, `U` (selenocysteine), `O` (pyrrolysine), and the ambiguity codes `BJZX`.
+    pub fn protein() -> Self {
+        Self::custom(b"ACDEFGHIKLMNPQRSTVWYUOBJZX")
+    }
+    pub fn contains(&self, symbol: u8) -> bool {
+        self.members[symbol as usize]
+    }
+    /// Return the symbols in ascending order.
+    pub fn symbols(&self) -> Vec<u8> {
+        (0..=255).filter(|&c| self.contains(c)).collect()
+    }
+    /// Return the number of the symbols.
+    pub fn len(&self) -> usize {
+        self.members.iter().filter(|&&member| member).count()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+    /// Return the first byte of `seq` out of the alphabet, if any.
+    pub fn validate(&self, seq: &[u8]) -> Result<(), InvalidSymbol> {
+        match seq.iter().position(|&c| !self.contains(c)) {
+            Some(position) => Err(InvalidSymbol {
+                position,
+                symbol: seq[position],
+            }),
+            None => Ok(()),
+        }
+    }
+    // Map a byte into the alphabet by case folding and U/T conversion.
+    fn normalize_symbol(&self, c: u8) -> Option<u8> {
+        if self.contains(c) {
+            return Some(c);
+        }
+        let upper = c.to_ascii_uppercase();
+        if self.contains(upper) {
+            return Some(upper);
+        }
+        match upper {
+            b'U' if self.contains(b'T') => Some(b'T'),
+            b'T' if self.contains(b'U') => Some(b'U'),
+            _ => None,
+        }
+    }
+    /// Return the normalized copy of `seq` (see the [module documentation](self)),
+    /// or the first byte that can not be mapped into the alphabet.
+    pub fn normalize(&self, seq: &[u8]) -> Result<Vec<u8>, InvalidSymbol> {
+        seq.iter()
+            .enumerate()
+            .map(|(position, &symbol)| {
+                self.normalize_symbol(symbol)
+                    .ok_or(InvalidSymbol { position, symbol })
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn alphabet_test() {
+        let dna = Alphabet::dna();
+        assert_eq!(dna.validate(b"ACGTTGCA"), Ok(()));
+        assert_eq!(
+            dna.validate(b"ACGTtGCA"),
+            Err(InvalidSymbol {
+                position: 4,
+                symbol: b't'
+            })
+        );
+        assert_eq!(dna.normalize(b"acgUN").unwrap_err().position(), 4);
+        assert_eq!(dna.normalize(b"acgUt").unwrap(), b"ACGTT");
+        assert_eq!(Alphabet::rna().normalize(b"ACGT").unwrap(), b"ACGU");
+        // Both `T` and `U` are amino acids.
+        let protein = Alphabet::protein();
+        assert_eq!(protein.len(), 26);
+        assert_eq!(protein.normalize(b"mtuk").unwrap(), b"MTUK");
+        assert_eq!(protein.validate(b"MTK*").unwrap_err().symbol(), b'*');
+        assert_eq!(Alphabet::iupac().normalize(b"acgtn").unwrap(), b"ACGTN");
+        // Lowercase symbols of a custom alphabet are kept as they are.
+        let custom = Alphabet::custom(b"ab");
+        assert_eq!(custom.normalize(b"ab").unwrap(), b"ab");
+        assert_eq!(custom.normalize(b"AB").unwrap_err().symbol(), b'A');
+        assert_eq!(format!("{:?}", custom), "Alphabet(\"ab\")");
+        assert_eq!(
+            custom.validate(b"a\tb").unwrap_err().to_string(),
+            "invalid symbol '\\t' at 1"
+        );
+    }
+}
@@ -0,0 +1,306 @@
+//! Banded global alignment around a known diagonal.
+//!
+//! The band is computed by Myers' bit-parallel algorithm on blocks of [`WORD_SIZE`] query characters, as edlib does,
+//! but only the blocks crossing the band are computed in each column of the target.
+use crate::myers::{Block, WORD_SIZE};
+use crate::{alphabet_len, AlignMode, AlignTask, Alignment, TracebackStrategy};
+
+// The computed blocks of each column of the target, to trace back the alignment.
+// The cells out of the blocks are taken as the DP does: the row just above the first block of a column is
+// one more than its left neighbor, and the rows below the last block increase by one downwards.
+struct Columns {
+    // The first block of each column, and the offset of the column in `blocks`.
+    firsts: Vec<(usize, usize)>,
+    blocks: Vec<Block>,
+}
+
+impl Columns {
+    fn column(&self, j: usize) -> (usize, &[Block]) {
+        let (first, offset) = self.firsts[j];
+        let end = self.firsts.get(j + 1).map_or(self.blocks.len(), |x| x.1);
+        (first, &self.blocks[offset..end])
+    }
+    // Return the score of the cell at `(i, j)`, or None if the DP did not take it into account.
+    fn score(&self, i: usize, mut j: usize) -> Option<u32> {
+        // The steps to the left along the row above the first block.
+        let mut left = 0;
+        loop {
+            if i == 0 || j == 0 {
+                return Some((i + j) as u32 + left);
+            }
+            let (first, blocks) = self.column(j);
+            let b = (i - 1) / WORD_SIZE;
+            if b < first {
+                if i != first * WORD_SIZE {
+                    return None;
+                }
+                left += 1;
+                j -= 1;
+                continue;
+            }
+            let score = match blocks.get(b - first) {
+                Some(block) => block.score_at((i - 1) % WORD_SIZE),
+                None => {
+                    let bottom = (first + blocks.len()) * WORD_SIZE;
+                    let above = match blocks.last() {
+                        Some(block) => block.score,
+                        None => j as u32,
+                    };
+                    above + (i - bottom) as u32
+                }
+            };
+            return Some(score + left);
+        }
+    }
+}
+
+/// Align the `query` to the `target` globally, restricting the alignment path to the diagonal band
+/// `|j - i - diagonal_offset| <= band`, where `i` and `j` are the positions on the query and the target.
+/// The returned alignment is computed with [`AlignTask::Alignment`].
+///
+/// The returned flag is true if the optimal alignment might leave the band,
+/// i.e., the alignment path in the band touches its boundary, or the band does not contain
+/// both the start and the end of the sequences (in that case, the alignment is not found).
+/// The band is computed bit-parallel in blocks of 64 query characters, thus it is widened to the blocks
+/// and the path may go slightly beyond it, which is reported by the flag as well.
+/// The computation takes `O(|target| * band / 64)` time and memory, independent of the distance.
+/// # Example
+/// ```
+/// use edlib_sys::align_banded;
+/// let query =  b"ACGTACGTTTACGTACGT";
+/// let target = b"ACGTACGTACGTACGT";
+/// let (aln, leaves_band) = align_banded(query, target, 3, -1);
+/// assert_eq!(aln.dist(), 2);
+/// assert!(!leaves_band);
+/// // The path reaches the boundary of the band.
+/// let (aln, leaves_band) = align_banded(query, target, 2, 0);
+/// assert!(aln.is_found() && leaves_band);
+/// ```
+pub fn align_banded(
+    query: &[u8],
+    target: &[u8],
+    band: usize,
+    diagonal_offset: isize,
+) -> (Alignment, bool) {
+    let (n, m) = (query.len(), target.len());
+    let mut aln = Alignment {
+        dist: None,
+        starts: Vec::new(),
+        ends: Vec::new(),
+        operations: Vec::new(),
+        query_location: Some((0, n as i32 - 1)),
+        strategy: None,
+        k: None,
+        rounds: 0,
+        alphabet_len: alphabet_len(query, target),
+        task: AlignTask::Alignment,
+        mode: AlignMode::Global,
+    };
+    // A band wider than `max(n, m)` around the offset covers every diagonal of the sequences,
+    // so it is clamped before the conversion to `isize`.
+    let widest = n
+        .max(m)
+        .saturating_add(diagonal_offset.unsigned_abs())
+        .min(isize::MAX as usize);
+    let (band, offset) = (band.min(widest) as isize, diagonal_offset);
+    if (band as usize) < offset.unsigned_abs()
+        || (band as i128) < (m as i128 - n as i128 - offset as i128).abs()
+    {
+        return (aln, true);
+    }
+    // The blocks of the query crossing the band in the j-th column, i.e., the rows `j - offset - band..=j - offset + band`.
+    let block_count = n.div_ceil(WORD_SIZE);
+    let blocks_at = |j: usize| {
+        let lower = (j as isize - offset - band).max(1);
+        let upper = (j as isize - offset + band).min(n as isize);
+        match lower <= upper {
+            true => (lower as usize - 1) / WORD_SIZE..(upper as usize - 1) / WORD_SIZE + 1,
+            false => 0..0,
+        }
+    };
+    // The equality bit-vectors of each block, by the index of the character in the query.
+    // The characters out of the query have the index 0, without any equalities.
+    let mut index = [0u16; 256];
+    let mut peq = vec![0u64; block_count];
+    for &c in query {
+        if index[c as usize] == 0 {
+            index[c as usize] = (peq.len() / block_count) as u16;
+            peq.resize(peq.len() + block_count, 0);
+        }
+    }
+    for (i, &c) in query.iter().enumerate() {
+        peq[index[c as usize] as usize * block_count + i / WORD_SIZE] |= 1 << (i % WORD_SIZE);
+    }
+    // The first column scores the query positions from zero.
+    let mut current: Vec<_> = (0..block_count)
+        .map(|b| Block::ramp(((b + 1) * WORD_SIZE) as u32))
+        .collect();
+    let mut range = blocks_at(0);
+    let mut columns = Columns {
+        firsts: Vec::with_capacity(m + 1),
+        blocks: Vec::new(),
+    };
+    columns.firsts.push((range.start, 0));
+    columns.blocks.extend_from_slice(&current[range.clone()]);
+    for (j, &c) in target.iter().enumerate() {
+        let next = blocks_at(j + 1);
+        // The blocks entering the band continue the last block of the previous column downwards.
+        let entering = range.end.max(next.start);
+        for b in entering..next.end {
+            let above = match b {
+                0 => j as u32,
+                _ if entering < b || range.contains(&(b - 1)) => current[b - 1].score,
+                _ => (j + b * WORD_SIZE) as u32,
+            };
+            current[b] = Block::ramp(above + WORD_SIZE as u32);
+        }
+        let eq = &peq[index[c as usize] as usize * block_count..];
+        // The cell above the first block is one more than its left neighbor, as in the first row.
+        let mut hin = 1;
+        for b in next.clone() {
+            hin = current[b].advance(eq[b], hin);
+        }
+        range = next;
+        columns.firsts.push((range.start, columns.blocks.len()));
+        columns.blocks.extend_from_slice(&current[range.clone()]);
+    }
+    let dist = columns.score(n, m).unwrap();
+    // Trace back, checking whether the path touches the boundary of the band.
+    let (mut i, mut j) = (n, m);
+    let mut leaves_band = false;
+    let mut operations = Vec::with_capacity(n + m);
+    while 0 < i || 0 < j {
+        let diagonal = j as isize - i as isize - offset;
+        leaves_band |= (diagonal <= -band && 0 < j) || (band <= diagonal && j < m);
+        let score = columns.score(i, j).unwrap();
+        let is_match = 0 < i && 0 < j && query[i - 1] == target[j - 1];
+        let diag = 0 < i
+            && 0 < j
+            && columns
+                .score(i - 1, j - 1)
+                .is_some_and(|diag| diag + (!is_match) as u32 == score);
+        let up = 0 < i && columns.score(i - 1, j).is_some_and(|up| up + 1 == score);
+        if diag {
+            operations.push(if is_match { 0 } else { 3 });
+            i -= 1;
+            j -= 1;
+        } else if up || j == 0 {
+            operations.push(1);
+            i -= 1;
+        } else {
+            operations.push(2);
+            j -= 1;
+        }
+    }
+    operations.reverse();
+    aln.dist = Some(dist);
+    aln.starts = vec![0];
+    aln.ends = vec![m as i32 - 1];
+    aln.operations = operations;
+    aln.strategy = Some(TracebackStrategy::Traceback);
+    (aln, leaves_band)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{align, align_banded, AlignMode, AlignTask};
+    #[test]
+    fn banded_test() {
+        let query = b"ACGTTGCAGGCATCGATCGACTAGCATCGACTAGC";
+        let target = b"ACGTTGCAGCATCGATCGATTCTAGCATCGGACTAGC";
+        let expected = align(query, target, AlignMode::Global, AlignTask::Alignment);
+        for offset in [0, 1, 2] {
+            let (aln, leaves_band) = align_banded(query, target, 4, offset);
+            assert_eq!(aln.dist(), expected.dist());
+            assert!(!leaves_band);
+            let ops = aln.operations().unwrap();
+            assert_eq!(ops.iter().filter(|&&op| op != 0).count() as u32, aln.dist());
+            assert_eq!(ops.iter().filter(|&&op| op != 2).count(), query.len());
+            assert_eq!(ops.iter().filter(|&&op| op != 1).count(), target.len());
+        }
+        // The end of the sequences is out of the band.
+        let (aln, leaves_band) = align_banded(query, target, 1, 0);
+        assert!(!aln.is_found() && leaves_band);
+        let (aln, leaves_band) = align_banded(b"", b"", 0, 0);
+        assert_eq!(aln.dist(), 0);
+        assert!(!leaves_band);
+        // A band wider than the sequences is clamped, rather than overflowing `isize`.
+        for offset in [-3, 0, 5] {
+            let (aln, leaves_band) = align_banded(query, target, usize::MAX, offset);
+            assert_eq!(aln.dist(), expected.dist());
+            assert!(!leaves_band);
+        }
+        let (aln, leaves_band) = align_banded(query, target, 100, isize::MIN);
+        assert!(!aln.is_found() && leaves_band);
+    }
+    // The distance restricted to the band by a plain dynamic programming.
+    fn naive(query: &[u8], target: &[u8], band: isize, offset: isize) -> Option<u32> {
+        let inf = u32::MAX / 2;
+        let in_band = |i: usize, j: usize| (j as isize - i as isize - offset).abs() <= band;
+        let mut dp = vec![vec![inf; target.len() + 1]; query.len() + 1];
+        for i in 0..=query.len() {
+            for j in (0..=target.len()).filter(|&j| in_band(i, j)) {
+                dp[i][j] = match (i, j) {
+                    (0, 0) => 0,
+                    (0, _) => dp[0][j - 1] + 1,
+                    (_, 0) => dp[i - 1][0] + 1,
+                    _ => (dp[i - 1][j - 1] + (query[i - 1] != target[j - 1]) as u32)
+                        .min(dp[i - 1][j] + 1)
+                        .min(dp[i][j - 1] + 1),
+                };
+            }
+        }
+        let dist = dp[query.len()][target.len()];
+        (dist < inf).then_some(dist)
+    }
+    #[test]
+    fn blocks_test() {
+        let mut x = 3u64;
+        let mut random = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    b"ACGT"[(x >> 62) as usize]
+                })
+                .collect()
+        };
+        for len in [0, 1, 63, 64, 65, 200, 300] {
+            let target = random(len);
+            // Mutate the target with a shift in the middle.
+            let mut query = target.clone();
+            for i in (0..query.len()).step_by(7) {
+                query[i] = b'A';
+            }
+            query.splice(len / 2..len / 2, random(len / 20));
+            let global = align(&query, &target, AlignMode::Global, AlignTask::Distance).dist();
+            for band in [0, 3, 10, 70] {
+                for offset in [-5, 0, 2] {
+                    let (aln, leaves_band) = align_banded(&query, &target, band, offset);
+                    let expected = naive(&query, &target, band as isize, offset);
+                    if !aln.is_found() {
+                        assert!(expected.is_none() && leaves_band);
+                        continue;
+                    }
+                    assert!(global <= aln.dist() && aln.dist() <= expected.unwrap());
+                    if !leaves_band {
+                        assert_eq!(aln.dist(), global);
+                    }
+                    // The operations align the sequences with the distance.
+                    let ops = aln.operations().unwrap();
+                    let (mut i, mut j, mut dist) = (0, 0, 0);
+                    for &op in ops {
+                        if op == 0 || op == 3 {
+                            assert_eq!(query[i] == target[j], op == 0);
+                        }
+                        i += (op != 2) as usize;
+                        j += (op != 1) as usize;
+                        dist += (op != 0) as u32;
+                    }
+                    assert_eq!((i, j, dist), (query.len(), target.len(), aln.dist()));
+                }
+            }
+        }
+    }
+}
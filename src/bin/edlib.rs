@@ -0,0 +1,469 @@
+//! A command-line aligner mirroring edlib's `edlib-aligner`.
+//! Run `edlib -h` to see the usage.
+use edlib_sys::io::{self, AlignedRecord, Driver, Record};
+use edlib_sys::{AlignConfig, AlignMode, AlignTask, Alignment, CigarFormat};
+use std::io::{BufWriter, Write};
+
+const USAGE: &str = "Usage: edlib [options...] <queries.fasta> <target.fasta>
+Align every query sequence to every target sequence. Input files are plain FASTA or FASTQ.
+Options:
+  -m NW|SHW|HW  Alignment mode: global, prefix, or infix. [default: NW]
+  -k K          Upper bound of the edit distance. Negative means no bound. [default: -1]
+  -p            Compute the alignment path.
+  -l            Compute the start and end locations.
+  -f FORMAT     Output format: text, cigar, cigar-std, sam, paf, or tsv. [default: tsv]
+                text, cigar, cigar-std and sam imply -p, and paf implies -l.
+  -r NAME       Align queries only to the target sequence named NAME.
+  -t THREADS    Number of threads. [default: 1]
+  -h            Print this message.";
+
+// Queries are processed in batches of this size per thread, to bound the memory usage.
+const BATCH_PER_THREAD: usize = 256;
+// The width of the text output.
+const LINE_WIDTH: usize = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Cigar(CigarFormat),
+    Sam,
+    Paf,
+    Tsv,
+}
+
+#[derive(Debug)]
+struct Args {
+    queries: String,
+    targets: String,
+    target_name: Option<String>,
+    config: AlignConfig,
+    format: Format,
+    threads: usize,
+}
+
+// Parse the command-line arguments. Return None if the usage is requested.
+fn parse_args(args: &[String]) -> Result<Option<Args>, String> {
+    let mut mode = AlignMode::Global;
+    let mut task = AlignTask::Distance;
+    let mut k: i64 = -1;
+    let mut format = Format::Tsv;
+    let mut threads = 1;
+    let mut target_name = None;
+    let mut files = vec![];
+    let mut args = args.iter();
+    while let Some(arg) = args.next() {
+        let mut value = |name: &str| {
+            args.next()
+                .ok_or_else(|| format!("option {} requires a value", name))
+        };
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(None),
+            "-m" => {
+                mode = match value("-m")?.as_str() {
+                    "NW" => AlignMode::Global,
+                    "SHW" => AlignMode::Prefix,
+                    "HW" => AlignMode::Infix,
+                    m => return Err(format!("invalid mode: {}", m)),
+                }
+            }
+            "-k" => {
+                k = value("-k")?
+                    .parse()
+                    .map_err(|e| format!("invalid -k: {}", e))?
+            }
+            "-p" => task = AlignTask::Alignment,
+            "-l" if task == AlignTask::Distance => task = AlignTask::Location,
+            "-l" => {}
+            "-f" => {
+                format = match value("-f")?.as_str() {
+                    "text" => Format::Text,
+                    "cigar" => Format::Cigar(CigarFormat::Extended),
+                    "cigar-std" => Format::Cigar(CigarFormat::Standard),
+                    "sam" => Format::Sam,
+                    "paf" => Format::Paf,
+                    "tsv" => Format::Tsv,
+                    f => return Err(format!("invalid format: {}", f)),
+                }
+            }
+            "-r" => target_name = Some(value("-r")?.clone()),
+            "-t" => {
+                threads = value("-t")?
+                    .parse()
+                    .map_err(|e| format!("invalid -t: {}", e))?;
+                if threads == 0 {
+                    return Err("invalid -t: the number of threads must be positive".to_string());
+                }
+            }
+            _ if arg.starts_with('-') && arg.len() > 1 => {
+                return Err(format!("unknown option: {}", arg))
+            }
+            _ => files.push(arg.clone()),
+        }
+    }
+    if let [queries, targets] = files.as_slice() {
+        match format {
+            Format::Text | Format::Cigar(_) | Format::Sam => task = AlignTask::Alignment,
+            Format::Paf if task == AlignTask::Distance => task = AlignTask::Location,
+            _ => {}
+        }
+        let mut config = AlignConfig::new(mode, task);
+        if 0 <= k {
+            config = config.with_k(k.min(u32::MAX as i64) as u32);
+        }
+        Ok(Some(Args {
+            queries: queries.clone(),
+            targets: targets.clone(),
+            target_name,
+            config,
+            format,
+            threads,
+        }))
+    } else {
+        Err(format!(
+            "expected two input files, found {}\n{}",
+            files.len(),
+            USAGE
+        ))
+    }
+}
+
+fn main() {
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    let args = match parse_args(&args) {
+        Ok(Some(args)) => args,
+        Ok(None) => {
+            println!("{}", USAGE);
+            return;
+        }
+        Err(msg) => {
+            eprintln!("{}", msg);
+            std::process::exit(1);
+        }
+    };
+    if let Err(why) = run(&args) {
+        eprintln!("edlib: {}", why);
+        std::process::exit(1);
+    }
+}
+
+fn run(args: &Args) -> std::io::Result<()> {
+    let targets = io::read_all(&args.targets)?;
+    let mut driver = Driver::with_config(&targets, args.config.clone());
+    if let Some(name) = args.target_name.as_deref() {
+        driver = driver.only(name).ok_or_else(|| {
+            let msg = format!("no target named {} in {}", name, args.targets);
+            std::io::Error::new(std::io::ErrorKind::NotFound, msg)
+        })?;
+    }
+    let stdout = std::io::stdout();
+    let mut wtr = BufWriter::new(stdout.lock());
+    match args.format {
+        Format::Sam => write_sam_header(&mut wtr, driver.references())?,
+        Format::Tsv => writeln!(wtr, "query\ttarget\tdist\tstart\tend\tcigar")?,
+        _ => {}
+    }
+    let mut queries = io::open(&args.queries)?;
+    let batch_size = BATCH_PER_THREAD * args.threads;
+    loop {
+        let batch = queries
+            .by_ref()
+            .take(batch_size)
+            .collect::<std::io::Result<Vec<_>>>()?;
+        if batch.is_empty() {
+            break;
+        }
+        let chunk_size = batch.len().div_ceil(args.threads);
+        let outputs = std::thread::scope(|scope| {
+            let handles: Vec<_> = batch
+                .chunks(chunk_size)
+                .map(|chunk| {
+                    let driver = &driver;
+                    scope.spawn(move || {
+                        let mut output = String::new();
+                        for query in chunk {
+                            let aligned =
+                                driver.align(query.clone()).map_err(std::io::Error::other)?;
+                            format_record(&mut output, &aligned, args.format);
+                        }
+                        Ok(output)
+                    })
+                })
+                .collect();
+            handles
+                .into_iter()
+                .map(|h| h.join().unwrap())
+                .collect::<std::io::Result<Vec<String>>>()
+        })?;
+        for output in outputs {
+            wtr.write_all(output.as_bytes())?;
+        }
+    }
+    wtr.flush()
+}
+
+fn write_sam_header<W: Write>(wtr: &mut W, targets: &[&Record]) -> std::io::Result<()> {
+    writeln!(wtr, "@HD\tVN:1.6\tSO:unsorted")?;
+    for target in targets {
+        writeln!(wtr, "@SQ\tSN:{}\tLN:{}", target.id(), target.seq().len())?;
+    }
+    writeln!(
+        wtr,
+        "@PG\tID:edlib\tPN:edlib\tVN:{}",
+        env!("CARGO_PKG_VERSION")
+    )
+}
+
+fn format_record(output: &mut String, aligned: &AlignedRecord, format: Format) {
+    use std::fmt::Write;
+    let query = &aligned.query;
+    // The best hit is the primary one in the SAM format.
+    let best = aligned
+        .alignments
+        .iter()
+        .enumerate()
+        .filter(|(_, (_, aln))| aln.is_found())
+        .min_by_key(|(_, (_, aln))| aln.dist())
+        .map(|(i, _)| i);
+    if best.is_none() && format == Format::Sam {
+        let _ = writeln!(output, "{}", sam_unmapped_line(query));
+    }
+    for (i, (target, aln)) in aligned.alignments.iter().enumerate() {
+        if !aln.is_found() && format != Format::Tsv {
+            continue;
+        }
+        let _ = match format {
+            Format::Tsv => writeln!(output, "{}", tsv_line(query, target, aln)),
+            Format::Paf => writeln!(output, "{}", paf_line(query, target, aln)),
+            Format::Sam => writeln!(output, "{}", sam_line(query, target, aln, best == Some(i))),
+            Format::Cigar(cigar) => writeln!(
+                output,
+                "{}\t{}\t{}\t{}",
+                query.id(),
+                target.id(),
+                aln.dist(),
+                aln.cigar(cigar).unwrap()
+            ),
+            Format::Text => writeln!(output, "{}", text_block(query, target, aln)),
+        };
+    }
+}
+
+fn location_or_star(aln: &Alignment) -> (String, String) {
+    match aln.location() {
+        Some((start, end)) => (start.to_string(), end.to_string()),
+        None => ("*".to_string(), "*".to_string()),
+    }
+}
+
+fn tsv_line(query: &Record, target: &Record, aln: &Alignment) -> String {
+    let dist = match aln.is_found() {
+        true => aln.dist().to_string(),
+        false => "*".to_string(),
+    };
+    let (start, end) = location_or_star(aln);
+    let cigar = aln.cigar(CigarFormat::Extended);
+    let cigar = cigar.as_deref().unwrap_or("*");
+    let (qid, tid) = (query.id(), target.id());
+    format!("{qid}\t{tid}\t{dist}\t{start}\t{end}\t{cigar}")
+}
+
+fn paf_line(query: &Record, target: &Record, aln: &Alignment) -> String {
+    let qlen = query.seq().len();
+    let tlen = target.seq().len();
+    // The task is at least `Location` for PAF. The end of the location is inclusive,
+    // while PAF uses exclusive ends.
+    let (tstart, tend) = aln.location().unwrap();
+    let tend = tend.wrapping_add(1);
+    let dist = aln.dist() as usize;
+    let (matches, block_len) = match aln.operations() {
+        Some(ops) => (ops.iter().filter(|&&op| op == 0).count(), ops.len()),
+        None => (qlen.saturating_sub(dist), qlen.max(tend - tstart)),
+    };
+    let mut line = format!(
+        "{}\t{}\t0\t{}\t+\t{}\t{}\t{}\t{}\t{}\t{}\t255\tNM:i:{}",
+        query.id(),
+        qlen,
+        qlen,
+        target.id(),
+        tlen,
+        tstart,
+        tend,
+        matches,
+        block_len,
+        dist
+    );
+    if let Some(cigar) = aln.cigar(CigarFormat::Extended) {
+        line.push_str("\tcg:Z:");
+        line.push_str(&cigar);
+    }
+    line
+}
+
+// The SEQ and QUAL fields of a SAM record, where `*` stands for an empty or missing field.
+fn sam_seq_qual(query: &Record) -> (String, String) {
+    if query.seq().is_empty() {
+        return ("*".to_string(), "*".to_string());
+    }
+    let seq = String::from_utf8_lossy(query.seq()).into_owned();
+    let qual = query.qual().map(String::from_utf8_lossy);
+    (seq, qual.unwrap_or("*".into()).into_owned())
+}
+
+fn sam_line(query: &Record, target: &Record, aln: &Alignment, is_primary: bool) -> String {
+    let flag = if is_primary { 0 } else { 256 };
+    let (start, _) = aln.location().unwrap_or((0, 0));
+    // A CIGAR of deletions only is invalid, hence unavailable for an empty query.
+    let cigar = match query.seq().is_empty() {
+        true => "*".to_string(),
+        false => aln.cigar(CigarFormat::Extended).unwrap(),
+    };
+    let (seq, qual) = sam_seq_qual(query);
+    format!(
+        "{}\t{}\t{}\t{}\t255\t{}\t*\t0\t0\t{}\t{}\tNM:i:{}",
+        query.id(),
+        flag,
+        target.id(),
+        start + 1,
+        cigar,
+        seq,
+        qual,
+        aln.dist()
+    )
+}
+
+// A query aligned to no target is reported as unmapped (flag 4).
+fn sam_unmapped_line(query: &Record) -> String {
+    let (seq, qual) = sam_seq_qual(query);
+    format!("{}\t4\t*\t0\t0\t*\t*\t0\t0\t{}\t{}", query.id(), seq, qual)
+}
+
+fn text_block(query: &Record, target: &Record, aln: &Alignment) -> String {
+    let (start, end) = aln.location().unwrap();
+    let ops = aln.operations().unwrap();
+    let (mut target_row, mut match_row, mut query_row) = (vec![], vec![], vec![]);
+    let (mut qpos, mut tpos) = (0, start);
+    let (qseq, tseq) = (query.seq(), target.seq());
+    for &op in ops {
+        let (q, t, m) = match op {
+            0 => (qseq[qpos], tseq[tpos], b'|'),
+            1 => (qseq[qpos], b'-', b' '),
+            2 => (b'-', tseq[tpos], b' '),
+            _ => (qseq[qpos], tseq[tpos], b' '),
+        };
+        qpos += (op != 2) as usize;
+        tpos += (op != 1) as usize;
+        target_row.push(t);
+        match_row.push(m);
+        query_row.push(q);
+    }
+    let mut block = format!(
+        "{} vs {}: dist={} target={}-{}\n",
+        query.id(),
+        target.id(),
+        aln.dist(),
+        start,
+        end
+    );
+    let rows = target_row
+        .chunks(LINE_WIDTH)
+        .zip(match_row.chunks(LINE_WIDTH))
+        .zip(query_row.chunks(LINE_WIDTH));
+    for ((t, m), q) in rows {
+        let (t, m, q) = (
+            String::from_utf8_lossy(t),
+            String::from_utf8_lossy(m),
+            String::from_utf8_lossy(q),
+        );
+        block.push_str(&format!("T: {}\n   {}\nQ: {}\n", t, m, q));
+    }
+    block
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use edlib_sys::align_with;
+    fn parse(args: &str) -> Result<Option<Args>, String> {
+        let args: Vec<String> = args.split_whitespace().map(|a| a.to_string()).collect();
+        parse_args(&args)
+    }
+    #[test]
+    fn parse_args_test() {
+        let args = parse("q.fa t.fa").unwrap().unwrap();
+        assert_eq!(
+            (args.queries.as_str(), args.targets.as_str()),
+            ("q.fa", "t.fa")
+        );
+        assert_eq!(
+            args.config,
+            AlignConfig::new(AlignMode::Global, AlignTask::Distance)
+        );
+        assert_eq!((args.format, args.threads), (Format::Tsv, 1));
+        let args = parse("-m HW -k 3 -l -r chr1 -t 2 q.fa t.fa")
+            .unwrap()
+            .unwrap();
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location).with_k(3);
+        assert_eq!(args.config, config);
+        assert_eq!(
+            (args.target_name.as_deref(), args.threads),
+            (Some("chr1"), 2)
+        );
+        // The output formats imply the tasks they need.
+        let task = |args: &str| parse(args).unwrap().unwrap().config.task();
+        assert_eq!(task("-f paf q.fa t.fa"), AlignTask::Location);
+        assert_eq!(task("-p -f paf q.fa t.fa"), AlignTask::Alignment);
+        assert_eq!(task("-f sam q.fa t.fa"), AlignTask::Alignment);
+        assert_eq!(task("-l -f cigar-std q.fa t.fa"), AlignTask::Alignment);
+        assert!(parse("-h").unwrap().is_none());
+        assert!(parse("-m XX q.fa t.fa").is_err());
+        assert!(parse("-k q.fa t.fa").is_err());
+        assert!(parse("-x q.fa t.fa").is_err());
+        assert!(parse("q.fa").is_err());
+        assert!(parse("-t").is_err());
+        assert!(parse("-t 0 q.fa t.fa").is_err());
+    }
+    #[test]
+    fn format_test() {
+        let query = Record::with_qual("q", None, b"ACGTACGT", b"IIIIIIII");
+        let target = Record::new("t", None, b"TTTTTTACGTACGTTTTTT");
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location);
+        let aln = align_with(query.seq(), target.seq(), &config);
+        assert_eq!(tsv_line(&query, &target, &aln), "q\tt\t0\t6\t13\t*");
+        let paf = "q\t8\t0\t8\t+\tt\t19\t6\t14\t8\t8\t255\tNM:i:0";
+        assert_eq!(paf_line(&query, &target, &aln), paf);
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Alignment);
+        let aln = align_with(b"ACGAACGT", target.seq(), &config);
+        let query = Record::with_qual("q", None, b"ACGAACGT", b"IIIIIIII");
+        let paf = "q\t8\t0\t8\t+\tt\t19\t6\t14\t7\t8\t255\tNM:i:1\tcg:Z:3=1X4=";
+        assert_eq!(paf_line(&query, &target, &aln), paf);
+        let sam = "q\t0\tt\t7\t255\t3=1X4=\t*\t0\t0\tACGAACGT\tIIIIIIII\tNM:i:1";
+        assert_eq!(sam_line(&query, &target, &aln, true), sam);
+        // An empty query has neither SEQ nor CIGAR.
+        let empty = Record::with_qual("e", None, b"", b"");
+        let aln = align_with(b"", target.seq(), &config);
+        let sam = "e\t0\tt\t1\t255\t*\t*\t0\t0\t*\t*\tNM:i:0";
+        assert_eq!(sam_line(&empty, &target, &aln, true), sam);
+        let aln = align_with(b"ACGAACGT", target.seq(), &config);
+        let block = text_block(&query, &target, &aln);
+        assert_eq!(
+            block,
+            "q vs t: dist=1 target=6-13\nT: ACGTACGT\n   ||| ||||\nQ: ACGAACGT\n"
+        );
+    }
+    #[test]
+    fn unmapped_test() {
+        let targets = vec![Record::new("t", None, b"TTTTTTTT")];
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment).with_k(1);
+        let driver = Driver::with_config(&targets, config);
+        let aligned = driver.align(Record::new("q", None, b"ACGT")).unwrap();
+        let mut output = String::new();
+        format_record(&mut output, &aligned, Format::Sam);
+        assert_eq!(output, "q\t4\t*\t0\t0\t*\t*\t0\t0\tACGT\t*\n");
+        let mut output = String::new();
+        format_record(&mut output, &aligned, Format::Paf);
+        assert!(output.is_empty());
+        let mut output = String::new();
+        format_record(&mut output, &aligned, Format::Tsv);
+        assert_eq!(output, "q\tt\t*\t*\t*\t*\n");
+    }
+}
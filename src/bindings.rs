@@ -2,10 +2,14 @@
 
 pub const EDLIB_STATUS_OK: u32 = 0;
 pub const EDLIB_STATUS_ERROR: u32 = 1;
+pub const EDLIB_STATUS_CANCELLED: u32 = 2;
 pub const EDLIB_EDOP_MATCH: u32 = 0;
 pub const EDLIB_EDOP_INSERT: u32 = 1;
 pub const EDLIB_EDOP_DELETE: u32 = 2;
 pub const EDLIB_EDOP_MISMATCH: u32 = 3;
+pub const EDLIB_STRATEGY_NONE: u32 = 0;
+pub const EDLIB_STRATEGY_TRACEBACK: u32 = 1;
+pub const EDLIB_STRATEGY_HIRSCHBERG: u32 = 2;
 #[doc = " Global method. This is the standard method."]
 #[doc = " Useful when you want to find out how similar is first sequence to second sequence."]
 pub const EdlibAlignMode_EDLIB_MODE_NW: EdlibAlignMode = 0;
@@ -126,12 +130,38 @@ pub struct EdlibAlignConfig {
     #[doc = " Number of additional equalities, which is non-negative number."]
     #[doc = " 0 if there are none."]
     pub additionalEqualitiesLength: ::std::os::raw::c_int,
+    #[doc = " Maximum size (in bytes) of data kept in memory for traceback when finding alignment path."]
+    #[doc = " If more is needed, Hirschberg's algorithm is used to split problem into smaller ones,"]
+    #[doc = " which needs less memory but is slower."]
+    #[doc = " Set to non-positive value to use the default of 1MB."]
+    pub memoryBudget: ::std::os::raw::c_longlong,
+    #[doc = " Function that is called periodically during computation, with cancelData as its argument."]
+    #[doc = " If it returns non-zero value, computation is stopped and result has status EDLIB_STATUS_CANCELLED."]
+    #[doc = " Can be set to NULL if computation should never be cancelled."]
+    pub shouldCancel: ::std::option::Option<
+        unsafe extern "C" fn(cancelData: *mut ::std::os::raw::c_void) -> ::std::os::raw::c_int,
+    >,
+    #[doc = " Data passed to shouldCancel."]
+    pub cancelData: *mut ::std::os::raw::c_void,
+    #[doc = " Function that is called periodically during calculation of edit distance, with progressData,"]
+    #[doc = " fraction of target columns processed (from 0 to 1) and k of current pass as its arguments."]
+    #[doc = " If k is auto-adjusted, target is processed again with doubled k until score is found."]
+    #[doc = " Can be set to NULL if progress should not be reported."]
+    pub reportProgress: ::std::option::Option<
+        unsafe extern "C" fn(
+            progressData: *mut ::std::os::raw::c_void,
+            progress: f64,
+            k: ::std::os::raw::c_int,
+        ),
+    >,
+    #[doc = " Data passed to reportProgress."]
+    pub progressData: *mut ::std::os::raw::c_void,
 }
 #[test]
 fn bindgen_test_layout_EdlibAlignConfig() {
     assert_eq!(
         ::std::mem::size_of::<EdlibAlignConfig>(),
-        32usize,
+        72usize,
         concat!("Size of: ", stringify!(EdlibAlignConfig))
     );
     assert_eq!(
@@ -224,10 +254,95 @@ fn bindgen_test_layout_EdlibAlignConfig() {
         );
     }
     test_field_additionalEqualitiesLength();
+    fn test_field_memoryBudget() {
+        assert_eq!(
+            unsafe {
+                let uninit = ::std::mem::MaybeUninit::<EdlibAlignConfig>::uninit();
+                let ptr = uninit.as_ptr();
+                ::std::ptr::addr_of!((*ptr).memoryBudget) as usize - ptr as usize
+            },
+            32usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(EdlibAlignConfig),
+                "::",
+                stringify!(memoryBudget)
+            )
+        );
+    }
+    test_field_memoryBudget();
+    fn test_field_shouldCancel() {
+        assert_eq!(
+            unsafe {
+                let uninit = ::std::mem::MaybeUninit::<EdlibAlignConfig>::uninit();
+                let ptr = uninit.as_ptr();
+                ::std::ptr::addr_of!((*ptr).shouldCancel) as usize - ptr as usize
+            },
+            40usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(EdlibAlignConfig),
+                "::",
+                stringify!(shouldCancel)
+            )
+        );
+    }
+    test_field_shouldCancel();
+    fn test_field_cancelData() {
+        assert_eq!(
+            unsafe {
+                let uninit = ::std::mem::MaybeUninit::<EdlibAlignConfig>::uninit();
+                let ptr = uninit.as_ptr();
+                ::std::ptr::addr_of!((*ptr).cancelData) as usize - ptr as usize
+            },
+            48usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(EdlibAlignConfig),
+                "::",
+                stringify!(cancelData)
+            )
+        );
+    }
+    test_field_cancelData();
+    fn test_field_reportProgress() {
+        assert_eq!(
+            unsafe {
+                let uninit = ::std::mem::MaybeUninit::<EdlibAlignConfig>::uninit();
+                let ptr = uninit.as_ptr();
+                ::std::ptr::addr_of!((*ptr).reportProgress) as usize - ptr as usize
+            },
+            56usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(EdlibAlignConfig),
+                "::",
+                stringify!(reportProgress)
+            )
+        );
+    }
+    test_field_reportProgress();
+    fn test_field_progressData() {
+        assert_eq!(
+            unsafe {
+                let uninit = ::std::mem::MaybeUninit::<EdlibAlignConfig>::uninit();
+                let ptr = uninit.as_ptr();
+                ::std::ptr::addr_of!((*ptr).progressData) as usize - ptr as usize
+            },
+            64usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(EdlibAlignConfig),
+                "::",
+                stringify!(progressData)
+            )
+        );
+    }
+    test_field_progressData();
 }
 extern "C" {
     #[doc = " Helper method for easy construction of configuration object."]
-    #[doc = " @return Configuration object filled with given parameters."]
+    #[doc = " @return Configuration object filled with given parameters, default memory budget, no cancellation and no progress reporting."]
     pub fn edlibNewAlignConfig(
         k: ::std::os::raw::c_int,
         mode: EdlibAlignMode,
@@ -245,7 +360,9 @@ extern "C" {
 #[repr(C)]
 #[derive(Debug, Copy, Clone)]
 pub struct EdlibAlignResult {
-    #[doc = " EDLIB_STATUS_OK or EDLIB_STATUS_ERROR. If error, all other fields will have undefined values."]
+    #[doc = " EDLIB_STATUS_OK, EDLIB_STATUS_ERROR or EDLIB_STATUS_CANCELLED."]
+    #[doc = " If error, all other fields will have undefined values."]
+    #[doc = " If cancelled, edit distance is -1 and no locations or alignment are set."]
     pub status: ::std::os::raw::c_int,
     #[doc = " -1 if k is non-negative and edit distance is larger than k."]
     pub editDistance: ::std::os::raw::c_int,
@@ -277,12 +394,22 @@ pub struct EdlibAlignResult {
     pub alignmentLength: ::std::os::raw::c_int,
     #[doc = " Number of different characters in query and target together."]
     pub alphabetLength: ::std::os::raw::c_int,
+    #[doc = " Strategy used to find alignment path: EDLIB_STRATEGY_TRACEBACK if traceback data of whole"]
+    #[doc = " problem fitted into memory budget, EDLIB_STRATEGY_HIRSCHBERG if it did not,"]
+    #[doc = " and EDLIB_STRATEGY_NONE if alignment path was not computed."]
+    pub alignmentStrategy: ::std::os::raw::c_int,
+    #[doc = " k used in the last pass of edit distance calculation, which is the given k if it is non-negative,"]
+    #[doc = " or the auto-adjusted k otherwise. -1 if there was no pass, e.g. if one of sequences is empty."]
+    pub finalK: ::std::os::raw::c_int,
+    #[doc = " Number of passes of edit distance calculation. If k is auto-adjusted, it is doubled in each pass,"]
+    #[doc = " starting from 64 (word size)."]
+    pub numRounds: ::std::os::raw::c_int,
 }
 #[test]
 fn bindgen_test_layout_EdlibAlignResult() {
     assert_eq!(
         ::std::mem::size_of::<EdlibAlignResult>(),
-        48usize,
+        64usize,
         concat!("Size of: ", stringify!(EdlibAlignResult))
     );
     assert_eq!(
@@ -426,6 +553,57 @@ fn bindgen_test_layout_EdlibAlignResult() {
         );
     }
     test_field_alphabetLength();
+    fn test_field_alignmentStrategy() {
+        assert_eq!(
+            unsafe {
+                let uninit = ::std::mem::MaybeUninit::<EdlibAlignResult>::uninit();
+                let ptr = uninit.as_ptr();
+                ::std::ptr::addr_of!((*ptr).alignmentStrategy) as usize - ptr as usize
+            },
+            48usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(EdlibAlignResult),
+                "::",
+                stringify!(alignmentStrategy)
+            )
+        );
+    }
+    test_field_alignmentStrategy();
+    fn test_field_finalK() {
+        assert_eq!(
+            unsafe {
+                let uninit = ::std::mem::MaybeUninit::<EdlibAlignResult>::uninit();
+                let ptr = uninit.as_ptr();
+                ::std::ptr::addr_of!((*ptr).finalK) as usize - ptr as usize
+            },
+            52usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(EdlibAlignResult),
+                "::",
+                stringify!(finalK)
+            )
+        );
+    }
+    test_field_finalK();
+    fn test_field_numRounds() {
+        assert_eq!(
+            unsafe {
+                let uninit = ::std::mem::MaybeUninit::<EdlibAlignResult>::uninit();
+                let ptr = uninit.as_ptr();
+                ::std::ptr::addr_of!((*ptr).numRounds) as usize - ptr as usize
+            },
+            56usize,
+            concat!(
+                "Offset of field: ",
+                stringify!(EdlibAlignResult),
+                "::",
+                stringify!(numRounds)
+            )
+        );
+    }
+    test_field_numRounds();
 }
 extern "C" {
     #[doc = " Frees memory in EdlibAlignResult that was allocated by edlib."]
@@ -0,0 +1,172 @@
+//! # Clustering by edit distance
+//!
+//! Collapse UMIs, barcodes, or amplicon sequence variants within a small edit distance.
+//! Two methods are provided:
+//!
+//! - [`greedy`]: Greedy centroid clustering. Sequences are visited in the decreasing order of the abundance,
+//!   and each of them joins the closest centroid within the distance, or becomes a new centroid.
+//! - [`directional`]: The directional method of UMI-tools. There is an edge from `a` to `b` if they
+//!   are within the distance and `count(a) >= 2 * count(b) - 1`. Each cluster consists of the sequences
+//!   reachable from the most abundant sequence not clustered yet.
+//!
+//! The distances are computed by [`crate::align_with`] in the [`AlignMode::Global`] mode with [`AlignTask::Distance`],
+//! capping `k` by the maximum distance.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::cluster::{directional, greedy};
+//! let umis: Vec<&[u8]> = vec![b"ACGTAC", b"ACGTAA", b"TTTTTT", b"ACGAAA"];
+//! let counts = [100, 3, 20, 1];
+//! let clusters = directional(&umis, &counts, 1);
+//! assert_eq!(clusters.assignments(), [0, 0, 1, 0].as_slice());
+//! assert_eq!(clusters.representatives(), [0, 2].as_slice());
+//! // ACGAAA is two edits away from the centroid ACGTAC.
+//! let clusters = greedy(&umis, &counts, 1);
+//! assert_eq!(clusters.assignments(), [0, 0, 1, 2].as_slice());
+//! ```
+use crate::{align_uncancellable, AlignConfig, AlignMode, AlignTask};
+
+/// The result of a clustering.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Clusters {
+    assignments: Vec<usize>,
+    representatives: Vec<usize>,
+}
+
+impl Clusters {
+    /// Return the cluster of each sequence, in the order of the input.
+    pub fn assignments(&self) -> &[usize] {
+        &self.assignments
+    }
+    /// Return the index of the representative sequence of each cluster.
+    /// Clusters are ordered by the abundance of their representatives.
+    pub fn representatives(&self) -> &[usize] {
+        &self.representatives
+    }
+    /// Return the number of the clusters.
+    pub fn len(&self) -> usize {
+        self.representatives.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.representatives.is_empty()
+    }
+    /// Return the members of each cluster.
+    pub fn members(&self) -> Vec<Vec<usize>> {
+        let mut members = vec![Vec::new(); self.len()];
+        for (i, &cluster) in self.assignments.iter().enumerate() {
+            members[cluster].push(i);
+        }
+        members
+    }
+}
+
+// Return the distance between `a` and `b` if it is at most `max_dist`.
+fn distance(a: &[u8], b: &[u8], max_dist: u32) -> Option<u32> {
+    let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance).with_k(max_dist);
+    let aln = align_uncancellable(a, b, &config);
+    aln.is_found().then(|| aln.dist())
+}
+
+// Return the indices in the decreasing order of the counts. Ties are broken by the index.
+fn by_abundance(counts: &[u64]) -> Vec<usize> {
+    let mut order: Vec<_> = (0..counts.len()).collect();
+    order.sort_by_key(|&i| std::cmp::Reverse(counts[i]));
+    order
+}
+
+/// Greedy centroid clustering. `counts[i]` is the abundance of `seqs[i]`.
+pub fn greedy(seqs: &[&[u8]], counts: &[u64], max_dist: u32) -> Clusters {
+    assert_eq!(seqs.len(), counts.len(), "The number of counts differs");
+    let mut assignments = vec![0; seqs.len()];
+    let mut representatives: Vec<usize> = Vec::new();
+    for i in by_abundance(counts) {
+        // (distance, cluster) of the closest centroid so far.
+        let mut closest: Option<(u32, usize)> = None;
+        for (cluster, &centroid) in representatives.iter().enumerate() {
+            // Only the centroids closer than the closest one so far are of interest.
+            let cap = closest.map_or(max_dist, |(d, _)| d - 1);
+            if let Some(d) = distance(seqs[i], seqs[centroid], cap) {
+                closest = Some((d, cluster));
+            }
+            if matches!(closest, Some((0, _))) {
+                break;
+            }
+        }
+        assignments[i] = match closest {
+            Some((_, cluster)) => cluster,
+            None => {
+                representatives.push(i);
+                representatives.len() - 1
+            }
+        };
+    }
+    Clusters {
+        assignments,
+        representatives,
+    }
+}
+
+/// Directional clustering of UMI-tools. `counts[i]` is the abundance of `seqs[i]`.
+pub fn directional(seqs: &[&[u8]], counts: &[u64], max_dist: u32) -> Clusters {
+    assert_eq!(seqs.len(), counts.len(), "The number of counts differs");
+    let n = seqs.len();
+    let mut edges = vec![Vec::new(); n];
+    for i in 0..n {
+        for j in i + 1..n {
+            let (ci, cj) = (counts[i], counts[j]);
+            let (forward, backward) = (2 * cj <= ci + 1, 2 * ci <= cj + 1);
+            if (forward || backward) && distance(seqs[i], seqs[j], max_dist).is_some() {
+                if forward {
+                    edges[i].push(j);
+                }
+                if backward {
+                    edges[j].push(i);
+                }
+            }
+        }
+    }
+    let mut assignments = vec![usize::MAX; n];
+    let mut representatives = Vec::new();
+    for root in by_abundance(counts) {
+        if assignments[root] != usize::MAX {
+            continue;
+        }
+        let cluster = representatives.len();
+        representatives.push(root);
+        assignments[root] = cluster;
+        let mut stack = vec![root];
+        while let Some(node) = stack.pop() {
+            for &next in edges[node].iter() {
+                if assignments[next] == usize::MAX {
+                    assignments[next] = cluster;
+                    stack.push(next);
+                }
+            }
+        }
+    }
+    Clusters {
+        assignments,
+        representatives,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn cluster_test() {
+        // A chain of UMIs, each one edit away from the previous one.
+        let umis: Vec<&[u8]> = vec![b"AAAAAA", b"AAAAAC", b"AAAACC", b"AAACCC", b"GGGGGG"];
+        let counts = [50, 20, 8, 3, 2];
+        let clusters = directional(&umis, &counts, 1);
+        assert_eq!(clusters.len(), 2);
+        assert_eq!(clusters.members(), vec![vec![0, 1, 2, 3], vec![4]]);
+        // The counts do not decrease fast enough.
+        let counts = [50, 30, 8, 3, 2];
+        let clusters = directional(&umis, &counts, 1);
+        assert_eq!(clusters.members(), vec![vec![0], vec![1, 2, 3], vec![4]]);
+        let clusters = greedy(&umis, &counts, 2);
+        assert_eq!(clusters.assignments(), [0, 0, 0, 1, 2].as_slice());
+        assert_eq!(clusters.representatives(), [0, 3, 4].as_slice());
+    }
+}
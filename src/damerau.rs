@@ -0,0 +1,382 @@
+//! Restricted Damerau-Levenshtein distance, where swapping two adjacent characters is a single edit.
+//!
+//! The distance is computed by Hyyrö's extension of Myers' bit-parallel algorithm,
+//! on blocks of [`WORD_SIZE`] rows of the dynamic programming table as in edlib.
+//! It is the optimal string alignment distance: a transposed pair can not be edited further,
+//! thus `CA` to `ABC` takes three edits instead of two.
+use crate::myers::WORD_SIZE;
+use crate::{alphabet_len, AlignConfig, AlignError, AlignMode, AlignTask, Alignment, Reporter};
+
+// The number of blocks processed between checks of the cancellation, as in edlib.
+const CHECK_INTERVAL: usize = 1 << 18;
+
+// The profile of the query split into blocks: the `i`-th bit of `peq[c * blocks + b]` is set
+// if the `(b * WORD_SIZE + i)`-th character of the query equals to `c`.
+struct BlockProfile {
+    peq: Vec<u64>,
+    blocks: usize,
+    len: usize,
+}
+
+impl BlockProfile {
+    fn new(query: &[u8], equalities: &[(u8, u8)]) -> Self {
+        let blocks = query.len().div_ceil(WORD_SIZE).max(1);
+        let mut peq = vec![0u64; 256 * blocks];
+        for (i, &c) in query.iter().enumerate() {
+            peq[c as usize * blocks + i / WORD_SIZE] |= 1 << (i % WORD_SIZE);
+        }
+        // The equalities are merged from the profile of the query itself, so that they are not transitive.
+        let original = peq.clone();
+        for &(a, b) in equalities.iter().filter(|(a, b)| a != b) {
+            for block in 0..blocks {
+                let (a, b) = (a as usize * blocks + block, b as usize * blocks + block);
+                peq[a] |= original[b];
+                peq[b] |= original[a];
+            }
+        }
+        Self {
+            peq,
+            blocks,
+            len: query.len(),
+        }
+    }
+    fn eq(&self, c: u8) -> &[u64] {
+        let start = c as usize * self.blocks;
+        &self.peq[start..start + self.blocks]
+    }
+    // Compute the last row of the table over the `target`, calling `visit(j, dist)` for each column `j`
+    // from 0 to `target.len()`. The first row is zero if `free_start`, and `0, 1, 2, ...` otherwise.
+    fn last_row(
+        &self,
+        target: &[u8],
+        free_start: bool,
+        config: &AlignConfig,
+        reporter: &Reporter<'_>,
+        mut visit: impl FnMut(usize, u32),
+    ) -> Result<(), AlignError> {
+        if self.len == 0 {
+            // The last row is the first row.
+            for j in 0..=target.len() {
+                visit(j, if free_start { 0 } else { j as u32 });
+            }
+            return Ok(());
+        }
+        let blocks = self.blocks;
+        // The bit of the last block holding the last row.
+        let last = (self.len - 1) % WORD_SIZE;
+        let (mut pv, mut mv) = (vec![!0u64; blocks], vec![0u64; blocks]);
+        // The diagonal zeros of the previous column, which are cleared to disable transpositions at the start.
+        let mut d0 = vec![!0u64; blocks];
+        let mut prev_eq: &[u64] = &vec![0u64; blocks];
+        let mut score = self.len as u32;
+        visit(0, score);
+        let check_interval = (CHECK_INTERVAL / blocks).max(1);
+        for (j, &c) in target.iter().enumerate() {
+            if j % check_interval == 0 {
+                if config.is_cancelled() {
+                    return Err(AlignError::Cancelled);
+                }
+                reporter.report(j as f64 / target.len() as f64, None);
+            }
+            let eq = self.eq(c);
+            // The horizontal difference entering the block from the row above.
+            let mut hin: i32 = if free_start { 0 } else { 1 };
+            // The transposition bit carried from the last row of the block above.
+            let mut tr_carry = 0;
+            for b in 0..blocks {
+                let swapped = !d0[b] & eq[b];
+                let tr = ((swapped << 1) | tr_carry) & prev_eq[b];
+                tr_carry = swapped >> (WORD_SIZE - 1);
+                let x = if hin < 0 { eq[b] | 1 } else { eq[b] };
+                let xh = ((x & pv[b]).wrapping_add(pv[b]) ^ pv[b]) | x;
+                let diag = xh | mv[b] | tr;
+                let mut ph = mv[b] | !(diag | pv[b]);
+                let mut mh = pv[b] & diag;
+                let out = if b + 1 == blocks { last } else { WORD_SIZE - 1 };
+                let hout = ((ph >> out) & 1) as i32 - ((mh >> out) & 1) as i32;
+                ph <<= 1;
+                mh <<= 1;
+                match hin {
+                    1 => ph |= 1,
+                    -1 => mh |= 1,
+                    _ => {}
+                }
+                pv[b] = mh | !(diag | ph);
+                mv[b] = ph & diag;
+                d0[b] = diag;
+                hin = hout;
+            }
+            prev_eq = eq;
+            score = (score as i32 + hin) as u32;
+            visit(j + 1, score);
+        }
+        reporter.report(1.0, None);
+        Ok(())
+    }
+    // Return the best distance in the last row and the columns attaining it.
+    fn best_columns(
+        &self,
+        target: &[u8],
+        free_start: bool,
+        config: &AlignConfig,
+        reporter: &Reporter<'_>,
+    ) -> Result<(u32, Vec<usize>), AlignError> {
+        let mut best = (u32::MAX, Vec::new());
+        self.last_row(target, free_start, config, reporter, |j, dist| {
+            if dist < best.0 {
+                best = (dist, Vec::new());
+            }
+            if dist == best.0 {
+                best.1.push(j);
+            }
+        })?;
+        Ok(best)
+    }
+}
+
+fn reversed(seq: &[u8]) -> Vec<u8> {
+    seq.iter().rev().copied().collect()
+}
+
+/// Align the `query` to the `target` under the `config`, allowing the transposition of two adjacent characters
+/// as a single edit in addition to insertions, deletions, and substitutions.
+/// The [`AlignMode::Global`], [`AlignMode::Prefix`], [`AlignMode::Infix`], and [`AlignMode::Suffix`] modes are
+/// supported as in [`crate::align_with`], while the others return [`AlignError::UnsupportedMode`].
+/// The distance is at most the edit distance.
+///
+/// The operations are not computed, as a transposition is not one of them:
+/// with [`AlignTask::Alignment`], the result is the same as [`AlignTask::Location`].
+/// If the distance exceeds the `k` of the `config`, the alignment is not found, and the start locations
+/// of the infix mode are not searched.
+/// # Example
+/// ```
+/// use edlib_sys::{align_damerau, align_with, AlignConfig, AlignMode, AlignTask};
+/// let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance);
+/// assert_eq!(align_with(b"ACGTTGCA", b"ACTGTGAC", &config).dist(), 4);
+/// assert_eq!(align_damerau(b"ACGTTGCA", b"ACTGTGAC", &config).unwrap().dist(), 2);
+/// let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location);
+/// let aln = align_damerau(b"GATTACA", b"CCCAGTTACACCC", &config).unwrap();
+/// assert_eq!(aln.dist(), 1);
+/// assert_eq!(aln.location(), Some((3, 9)));
+/// ```
+pub fn align_damerau(
+    query: &[u8],
+    target: &[u8],
+    config: &AlignConfig,
+) -> Result<Alignment, AlignError> {
+    if let AlignMode::Overlap | AlignMode::Local = config.mode {
+        return Err(AlignError::UnsupportedMode(config.mode));
+    }
+    let reporter = Reporter::new(config);
+    let m = target.len();
+    let task = match config.task {
+        AlignTask::Distance => AlignTask::Distance,
+        _ => AlignTask::Location,
+    };
+    // (distance, start, end), where the end is inclusive.
+    let (dist, locations): (u32, Vec<(usize, usize)>) = match config.mode {
+        AlignMode::Global => {
+            let profile = BlockProfile::new(query, &config.equalities);
+            let mut dist = 0;
+            profile.last_row(target, false, config, &reporter, |j, d| {
+                if j == m {
+                    dist = d;
+                }
+            })?;
+            (dist, vec![(0, m.wrapping_sub(1))])
+        }
+        AlignMode::Prefix => {
+            let profile = BlockProfile::new(query, &config.equalities);
+            let (dist, columns) = profile.best_columns(target, false, config, &reporter)?;
+            (
+                dist,
+                columns.iter().map(|&j| (0, j.wrapping_sub(1))).collect(),
+            )
+        }
+        AlignMode::Suffix => {
+            let profile = BlockProfile::new(&reversed(query), &config.equalities);
+            let (dist, columns) =
+                profile.best_columns(&reversed(target), false, config, &reporter)?;
+            (
+                dist,
+                columns
+                    .iter()
+                    .map(|&j| (m - j, m.wrapping_sub(1)))
+                    .collect(),
+            )
+        }
+        _ => {
+            let profile = BlockProfile::new(query, &config.equalities);
+            let (dist, columns) = profile.best_columns(target, true, config, &reporter)?;
+            let found = config.k.is_none_or(|k| dist <= k);
+            let locations = match task {
+                AlignTask::Location if found => {
+                    let profile = BlockProfile::new(&reversed(query), &config.equalities);
+                    // An alignment with `dist` edits spans at most `n + dist` target characters.
+                    let span = query.len() + dist as usize;
+                    let start = |j: usize| -> Result<(usize, usize), AlignError> {
+                        // The longest alignment ending at the column, so that it starts with
+                        // mismatches rather than insertions, as in edlib.
+                        let reversed_target = reversed(&target[j.saturating_sub(span)..j]);
+                        let (_, columns) =
+                            profile.best_columns(&reversed_target, false, config, &reporter)?;
+                        Ok((j - columns.last().unwrap(), j.wrapping_sub(1)))
+                    };
+                    columns
+                        .iter()
+                        .map(|&j| start(j))
+                        .collect::<Result<_, _>>()?
+                }
+                _ => Vec::new(),
+            };
+            (dist, locations)
+        }
+    };
+    let found = config.k.is_none_or(|k| dist <= k);
+    Ok(Alignment {
+        dist: found.then_some(dist),
+        starts: locations.iter().map(|&(start, _)| start as i32).collect(),
+        ends: locations.iter().map(|&(_, end)| end as i32).collect(),
+        operations: Vec::new(),
+        query_location: Some((0, query.len() as i32 - 1)),
+        strategy: None,
+        k: None,
+        rounds: 0,
+        alphabet_len: alphabet_len(query, target),
+        task,
+        mode: config.mode,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    // The restricted Damerau-Levenshtein distance by the plain dynamic programming.
+    fn naive(a: &[u8], b: &[u8]) -> u32 {
+        let mut d = vec![vec![0u32; b.len() + 1]; a.len() + 1];
+        for i in 0..=a.len() {
+            for j in 0..=b.len() {
+                d[i][j] = match (i, j) {
+                    (0, _) => j as u32,
+                    (_, 0) => i as u32,
+                    _ => {
+                        let sub = d[i - 1][j - 1] + (a[i - 1] != b[j - 1]) as u32;
+                        let mut best = sub.min(d[i - 1][j] + 1).min(d[i][j - 1] + 1);
+                        if 1 < i && 1 < j && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                            best = best.min(d[i - 2][j - 2] + 1);
+                        }
+                        best
+                    }
+                };
+            }
+        }
+        d[a.len()][b.len()]
+    }
+    #[test]
+    fn naive_test() {
+        let mut x = 7u64;
+        let mut random = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    b"ACG"[(x >> 62) as usize % 3]
+                })
+                .collect()
+        };
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance);
+        // Lengths around the block boundaries.
+        for (n, m) in [
+            (0, 5),
+            (5, 0),
+            (10, 12),
+            (63, 64),
+            (64, 66),
+            (65, 60),
+            (130, 140),
+        ] {
+            for _ in 0..20 {
+                let (a, b) = (random(n), random(m));
+                let aln = align_damerau(&a, &b, &config).unwrap();
+                assert_eq!(aln.dist(), naive(&a, &b), "{:?} {:?}", a, b);
+            }
+        }
+        // The free ends are the best over the substrings of the target.
+        for _ in 0..20 {
+            let (a, b) = (random(6), random(15));
+            let mut best = [u32::MAX; 3];
+            for start in 0..=b.len() {
+                for end in start..=b.len() {
+                    let dist = naive(&a, &b[start..end]);
+                    best[0] = best[0].min(dist);
+                    if start == 0 {
+                        best[1] = best[1].min(dist);
+                    }
+                    if end == b.len() {
+                        best[2] = best[2].min(dist);
+                    }
+                }
+            }
+            for (mode, best) in [AlignMode::Infix, AlignMode::Prefix, AlignMode::Suffix]
+                .iter()
+                .zip(best)
+            {
+                let config = AlignConfig::new(*mode, AlignTask::Location);
+                let aln = align_damerau(&a, &b, &config).unwrap();
+                assert_eq!(aln.dist(), best);
+                let (start, end) = aln.location().unwrap();
+                assert_eq!(naive(&a, &b[start..end.wrapping_add(1)]), best);
+            }
+        }
+    }
+    #[test]
+    fn damerau_test() {
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment);
+        let aln = align_damerau(b"CA", b"ABC", &config).unwrap();
+        assert_eq!(aln.dist(), 3);
+        assert_eq!(aln.location(), Some((0, 2)));
+        assert_eq!(aln.operations(), None);
+        assert!(!align_damerau(b"ACGT", b"TGCA", &config.clone().with_k(2))
+            .unwrap()
+            .is_found());
+        let config = AlignConfig::new(AlignMode::Prefix, AlignTask::Location);
+        let aln = align_damerau(b"CAGT", b"ACGTTTTT", &config).unwrap();
+        assert_eq!((aln.dist(), aln.location()), (1, Some((0, 3))));
+        let config = AlignConfig::new(AlignMode::Suffix, AlignTask::Location);
+        let aln = align_damerau(b"TTTGCA", b"AAAAATTTCGA", &config).unwrap();
+        assert_eq!((aln.dist(), aln.location()), (1, Some((5, 10))));
+        // The transposed pair spans two blocks of the query.
+        let query: Vec<u8> = b"ACGT".iter().cycle().take(100).copied().collect();
+        let mut target = query.clone();
+        target.swap(63, 64);
+        target.splice(0..0, b"GGGG".iter().copied());
+        target.extend_from_slice(b"CCC");
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location);
+        let aln = align_damerau(&query, &target, &config).unwrap();
+        assert_eq!((aln.dist(), aln.location()), (1, Some((4, 103))));
+        // `U` matches `T`, thus `GU` is a transposition of `TG`.
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance)
+            .with_equalities(&[(b'U', b'T')]);
+        assert_eq!(align_damerau(b"ACGU", b"ACTG", &config).unwrap().dist(), 1);
+        // `A` and `C` both equal `N`, but not each other.
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance)
+            .with_equalities(&[(b'N', b'A'), (b'N', b'C')]);
+        assert_eq!(align_damerau(b"AN", b"CC", &config).unwrap().dist(), 1);
+        assert_eq!(align_damerau(b"NA", b"AN", &config).unwrap().dist(), 0);
+        // The start is searched only near the end in a long target.
+        let mut target = vec![b'T'; 100_000];
+        target.splice(50_000..50_000, b"GATTACA".iter().copied());
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location);
+        let aln = align_damerau(b"GATTCAA", &target, &config).unwrap();
+        assert_eq!((aln.dist(), aln.location()), (1, Some((50_000, 50_006))));
+        assert!(!align_damerau(b"GATTCAA", &target, &config.with_k(0))
+            .unwrap()
+            .is_found());
+        for mode in [AlignMode::Overlap, AlignMode::Local] {
+            let config = AlignConfig::new(mode, AlignTask::Distance);
+            let result = align_damerau(b"ACGT", b"ACGT", &config);
+            assert_eq!(result.unwrap_err(), AlignError::UnsupportedMode(mode));
+        }
+    }
+}
@@ -0,0 +1,187 @@
+//! # Barcode demultiplexing
+//!
+//! Find which barcode in a whitelist best matches a read (or a segment of a read, such as the first
+//! hundred bases of a Nanopore read). Each barcode is searched in the read by infix alignment.
+//! The profiles of the barcodes are built once and reused for every read, as long as the barcodes are
+//! at most 64 bases long. Longer barcodes are aligned by [`crate::align_with`] each time.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::demux::Demultiplexer;
+//! let barcodes: Vec<&[u8]> = vec![b"AAGGTTCC", b"CCTTGGAA", b"ACACACAC"];
+//! let demux = Demultiplexer::new(&barcodes).with_max_dist(2).with_margin(1);
+//! let hit = demux.demultiplex(b"TTTTCCTTGGTATTTTTTTTTTT").unwrap();
+//! assert_eq!(hit.barcode(), 1);
+//! assert_eq!(hit.dist(), 1);
+//! assert_eq!(hit.location(), (4, 11));
+//! assert!(!hit.is_ambiguous());
+//! assert!(demux.demultiplex(b"GGGGGGGGGGGGGGGG").is_none());
+//! ```
+use crate::myers::Profile;
+use crate::{align_uncancellable, AlignConfig, AlignMode, AlignTask};
+
+/// The best barcode for a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BarcodeHit {
+    barcode: usize,
+    dist: u32,
+    location: (usize, usize),
+    runner_up: Option<(usize, u32)>,
+    ambiguous: bool,
+}
+
+impl BarcodeHit {
+    /// Return the index of the best barcode.
+    pub fn barcode(&self) -> usize {
+        self.barcode
+    }
+    /// Return the edit distance of the best barcode.
+    pub fn dist(&self) -> u32 {
+        self.dist
+    }
+    /// Return the range of the read the best barcode aligns to.
+    /// As in [`crate::Alignment::location()`], the end coordinate is inclusive.
+    pub fn location(&self) -> (usize, usize) {
+        self.location
+    }
+    /// Return the index and the distance of the second best barcode, if any within the maximum distance
+    /// or within the margin from the best barcode.
+    pub fn runner_up(&self) -> Option<(usize, u32)> {
+        self.runner_up
+    }
+    /// Return true if the runner-up is within the margin from the best barcode.
+    pub fn is_ambiguous(&self) -> bool {
+        self.ambiguous
+    }
+}
+
+/// A demultiplexer built from a whitelist of barcodes.
+#[derive(Debug, Clone)]
+pub struct Demultiplexer {
+    barcodes: Vec<Vec<u8>>,
+    profiles: Vec<Option<Profile>>,
+    max_dist: u32,
+    margin: u32,
+}
+
+impl Demultiplexer {
+    /// Create a demultiplexer. By default, the maximum distance is a quarter of the shortest barcode,
+    /// and a call is ambiguous only if the runner-up has the same distance as the best barcode.
+    pub fn new(barcodes: &[&[u8]]) -> Self {
+        let shortest = barcodes.iter().map(|b| b.len()).min().unwrap_or(0);
+        Self {
+            barcodes: barcodes.iter().map(|b| b.to_vec()).collect(),
+            profiles: barcodes.iter().map(|b| Profile::new(b, &[])).collect(),
+            max_dist: (shortest / 4) as u32,
+            margin: 0,
+        }
+    }
+    /// Set the maximum edit distance of a barcode to be reported.
+    pub fn with_max_dist(mut self, max_dist: u32) -> Self {
+        self.max_dist = max_dist;
+        self
+    }
+    /// Flag a call as ambiguous if the runner-up is at most `best + margin` away from the read.
+    pub fn with_margin(mut self, margin: u32) -> Self {
+        self.margin = margin;
+        self
+    }
+    /// Return the barcodes.
+    pub fn barcodes(&self) -> &[Vec<u8>] {
+        &self.barcodes
+    }
+    // The distance of the `i`-th barcode to the read, if it is at most `max_dist`.
+    fn distance(&self, i: usize, read: &[u8], max_dist: u32) -> Option<u32> {
+        match self.profiles[i].as_ref() {
+            Some(profile) => profile.search(read, max_dist).map(|(dist, _)| dist),
+            None => {
+                let config =
+                    AlignConfig::new(AlignMode::Infix, AlignTask::Distance).with_k(max_dist);
+                let aln = align_uncancellable(&self.barcodes[i], read, &config);
+                aln.is_found().then(|| aln.dist())
+            }
+        }
+    }
+    /// Find the best barcode for the `read`.
+    /// Return None if no barcode is within the maximum distance.
+    /// If two barcodes have the same distance, the first one in the whitelist is reported as the best.
+    pub fn demultiplex(&self, read: &[u8]) -> Option<BarcodeHit> {
+        // (distance, index) of the best and the second best barcodes.
+        let mut best: Option<(u32, usize)> = None;
+        let mut runner_up: Option<(u32, usize)> = None;
+        // The runner-up is searched within the maximum distance, and within the margin from the best.
+        let limit = |best: Option<(u32, usize)>| match best {
+            Some((dist, _)) => self.max_dist.max(dist.saturating_add(self.margin)),
+            None => self.max_dist.saturating_add(self.margin),
+        };
+        for i in 0..self.barcodes.len() {
+            // Barcodes worse than the runner-up do not change anything.
+            let cap = runner_up.map_or(limit(best), |(d, _)| d.saturating_sub(1));
+            let dist = match self.distance(i, read, cap) {
+                Some(dist) => dist,
+                None => continue,
+            };
+            match best {
+                Some((best_dist, _)) if best_dist <= dist => runner_up = Some((dist, i)),
+                _ => {
+                    runner_up = best;
+                    best = Some((dist, i));
+                }
+            }
+            if matches!(runner_up, Some((0, _))) {
+                break;
+            }
+        }
+        let (dist, barcode) = best.filter(|&(dist, _)| dist <= self.max_dist)?;
+        let runner_up = runner_up.filter(|&(d, _)| d <= limit(best));
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location).with_k(dist);
+        let location = align_uncancellable(&self.barcodes[barcode], read, &config).location()?;
+        let ambiguous = runner_up.is_some_and(|(d, _)| d <= dist + self.margin);
+        Some(BarcodeHit {
+            barcode,
+            dist,
+            location,
+            runner_up: runner_up.map(|(d, i)| (i, d)),
+            ambiguous,
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn demux_test() {
+        let long_barcode = b"ACGTTGCAACGTTGCAACGTTGCAACGTTGCAACGTTGCAACGTTGCAACGTTGCAACGTTGCAGGG";
+        let barcodes: Vec<&[u8]> = vec![b"AAAACCCC", b"AAAACCCG", long_barcode];
+        let demux = Demultiplexer::new(&barcodes)
+            .with_max_dist(3)
+            .with_margin(0);
+        // Both of the first two barcodes match with one edit.
+        let hit = demux.demultiplex(b"TTTTAAAACCCTTTT").unwrap();
+        assert_eq!((hit.barcode(), hit.dist()), (0, 1));
+        assert_eq!(hit.runner_up(), Some((1, 1)));
+        assert!(hit.is_ambiguous());
+        let hit = demux.demultiplex(b"TTAAAACCCGTT").unwrap();
+        assert_eq!((hit.barcode(), hit.dist(), hit.location()), (1, 0, (2, 9)));
+        assert_eq!(hit.runner_up(), Some((0, 1)));
+        assert!(!hit.is_ambiguous());
+        // The long barcode is aligned by edlib, with two bases deleted.
+        let mut read = b"TTTT".to_vec();
+        read.extend_from_slice(&long_barcode[..60]);
+        read.extend_from_slice(b"CAGGG");
+        let hit = demux.demultiplex(&read).unwrap();
+        assert_eq!((hit.barcode(), hit.dist()), (2, 2));
+        assert_eq!(hit.runner_up(), None);
+        // The runner-up beyond the maximum distance is still within the margin.
+        let demux = Demultiplexer::new(&barcodes[..2])
+            .with_max_dist(0)
+            .with_margin(1);
+        let hit = demux.demultiplex(b"TTAAAACCCGTT").unwrap();
+        assert_eq!((hit.barcode(), hit.dist()), (1, 0));
+        assert_eq!(hit.runner_up(), Some((0, 1)));
+        assert!(hit.is_ambiguous());
+        // The best barcode should be within the maximum distance regardless of the margin.
+        assert!(demux.demultiplex(b"TTAAAACCCTT").is_none());
+    }
+}
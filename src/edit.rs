@@ -0,0 +1,239 @@
+//! # Edit scripts
+//!
+//! Treat the alignment operations (see [`Alignment::operations()`]) as an edit script.
+//!
+//! - [`apply_ops`] rebuilds the query from the aligned part of the target.
+//! - [`unapply_ops`] rebuilds the aligned part of the target from the query.
+//! - [`validate`] checks that an alignment is consistent with both sequences and its distance.
+//!
+//! The operations do not tell which base is inserted or substituted, so those bases are given separately:
+//! the `bases` arguments are the bases of the sequence being rebuilt at the insertions and the mismatches, in order.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::edit::{apply_ops, unapply_ops, validate};
+//! use edlib_sys::{align, AlignMode, AlignTask};
+//! let (query, target) = (b"CGTCCG", b"ACGTCGT");
+//! let aln = align(query, target, AlignMode::Prefix, AlignTask::Alignment);
+//! let ops = aln.operations().unwrap();
+//! assert_eq!(ops, [2, 0, 0, 0, 0, 1, 0].as_slice());
+//! let (start, end) = aln.location().unwrap();
+//! // The inserted `C` of the query.
+//! assert_eq!(apply_ops(&target[start..=end], ops, b"C"), Some(query.to_vec()));
+//! // The deleted `A` of the target.
+//! assert_eq!(unapply_ops(query, ops, b"A"), Some(b"ACGTCG".to_vec()));
+//! assert_eq!(validate(query, target, &aln, &[]), Ok(()));
+//! ```
+use crate::Alignment;
+
+/// The reason why an alignment is inconsistent with the sequences.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum InvalidPath {
+    /// The operations or the locations are not available.
+    Unavailable,
+    /// The location is out of the sequence.
+    OutOfRange,
+    /// The `i`-th operation is not 0, 1, 2, or 3.
+    UnknownOperation(usize),
+    /// The `i`-th operation is a match of different bases, or a mismatch of equal bases.
+    WrongOperation(usize),
+    /// The operations consume `found` bases of the query, while the aligned part has `expected` bases.
+    QueryLength { expected: usize, found: usize },
+    /// The operations consume `found` bases of the target, while the aligned part has `expected` bases.
+    TargetLength { expected: usize, found: usize },
+    /// The operations contain `found` edits, while the alignment reports `expected`.
+    Distance { expected: u32, found: u32 },
+}
+
+impl std::fmt::Display for InvalidPath {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Unavailable => write!(f, "the operations are not available"),
+            Self::OutOfRange => write!(f, "the location is out of the sequence"),
+            Self::UnknownOperation(i) => write!(f, "unknown operation at {}", i),
+            Self::WrongOperation(i) => write!(f, "the operation at {} disagrees with the bases", i),
+            Self::QueryLength { expected, found } => {
+                write!(f, "{} query bases consumed, {} expected", found, expected)
+            }
+            Self::TargetLength { expected, found } => {
+                write!(f, "{} target bases consumed, {} expected", found, expected)
+            }
+            Self::Distance { expected, found } => {
+                write!(
+                    f,
+                    "{} edits in the operations, {} reported",
+                    found, expected
+                )
+            }
+        }
+    }
+}
+
+impl std::error::Error for InvalidPath {}
+
+// Rebuild a sequence from the other one (`from`) and the bases at the edits.
+// `dropped` is the operation consuming only `from`.
+fn rebuild(from: &[u8], ops: &[u8], bases: &[u8], dropped: u8) -> Option<Vec<u8>> {
+    let (mut from, mut bases) = (from.iter(), bases.iter());
+    let mut rebuilt = Vec::with_capacity(ops.len());
+    for &op in ops.iter() {
+        match op {
+            0 => rebuilt.push(*from.next()?),
+            3 => {
+                from.next()?;
+                rebuilt.push(*bases.next()?);
+            }
+            _ if op == dropped => {
+                from.next()?;
+            }
+            1 | 2 => rebuilt.push(*bases.next()?),
+            _ => return None,
+        }
+    }
+    (from.next().is_none() && bases.next().is_none()).then_some(rebuilt)
+}
+
+/// Rebuild the query from the aligned part of the `target` and the operations.
+/// `bases` are the query bases at the insertions and the mismatches.
+/// Return None if the operations do not consume exactly the `target` and the `bases`.
+pub fn apply_ops(target: &[u8], ops: &[u8], bases: &[u8]) -> Option<Vec<u8>> {
+    rebuild(target, ops, bases, 2)
+}
+
+/// Rebuild the aligned part of the target from the `query` and the operations, inverse to [`apply_ops`].
+/// `bases` are the target bases at the deletions and the mismatches.
+/// Return None if the operations do not consume exactly the `query` and the `bases`.
+pub fn unapply_ops(query: &[u8], ops: &[u8], bases: &[u8]) -> Option<Vec<u8>> {
+    rebuild(query, ops, bases, 1)
+}
+
+/// Check that the operations of `aln` align the aligned parts of `query` and `target`
+/// (see [`Alignment::location()`] and [`Alignment::query_location()`]) and have `aln.dist()` edits.
+/// `equalities` should be the same as those given by [`crate::AlignConfig::with_equalities`].
+pub fn validate(
+    query: &[u8],
+    target: &[u8],
+    aln: &Alignment,
+    equalities: &[(u8, u8)],
+) -> Result<(), InvalidPath> {
+    let ops = aln.operations().ok_or(InvalidPath::Unavailable)?;
+    // No locations mean that nothing is aligned, as in an overlap without overlap.
+    fn segment(seq: &[u8], location: Option<(usize, usize)>) -> Result<&[u8], InvalidPath> {
+        match location {
+            // The end is `-1 as usize` if the aligned part is empty.
+            Some((start, end)) => seq
+                .get(start..end.wrapping_add(1))
+                .ok_or(InvalidPath::OutOfRange),
+            None => Ok(&seq[..0]),
+        }
+    }
+    let target = segment(target, aln.location())?;
+    let query = segment(query, aln.query_location())?;
+    let is_equal =
+        |a: u8, b: u8| a == b || equalities.contains(&(a, b)) || equalities.contains(&(b, a));
+    let (mut qpos, mut tpos, mut edits) = (0, 0, 0);
+    for (i, &op) in ops.iter().enumerate() {
+        let (q, t) = (query.get(qpos).copied(), target.get(tpos).copied());
+        match op {
+            0 | 3 => {
+                if let (Some(q), Some(t)) = (q, t) {
+                    if is_equal(q, t) != (op == 0) {
+                        return Err(InvalidPath::WrongOperation(i));
+                    }
+                }
+                qpos += 1;
+                tpos += 1;
+            }
+            1 => qpos += 1,
+            2 => tpos += 1,
+            _ => return Err(InvalidPath::UnknownOperation(i)),
+        }
+        edits += (op != 0) as u32;
+    }
+    if qpos != query.len() {
+        let (expected, found) = (query.len(), qpos);
+        return Err(InvalidPath::QueryLength { expected, found });
+    }
+    if tpos != target.len() {
+        let (expected, found) = (target.len(), tpos);
+        return Err(InvalidPath::TargetLength { expected, found });
+    }
+    if edits != aln.dist() {
+        let (expected, found) = (aln.dist(), edits);
+        return Err(InvalidPath::Distance { expected, found });
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{align, align_with, AlignConfig, AlignMode, AlignTask};
+    #[test]
+    fn edit_test() {
+        let target = b"TTGACGTACCGTAGGGTACGTTTAGACCAGTACAGTTTTACGATCG";
+        let query = b"GTAGGCTACGTTAGACCAGTTCAG";
+        let equalities = [(b'N', b'A'), (b'N', b'C'), (b'N', b'G'), (b'N', b'T')];
+        let masked = b"GTAGNCTACGTTAGNNCAGTTCAG";
+        for mode in [
+            AlignMode::Global,
+            AlignMode::Prefix,
+            AlignMode::Infix,
+            AlignMode::Suffix,
+            AlignMode::Overlap,
+            AlignMode::Local,
+        ] {
+            let aln = align(query, target, mode, AlignTask::Alignment);
+            assert_eq!(validate(query, target, &aln, &[]), Ok(()), "{:?}", mode);
+            let config = AlignConfig::new(mode, AlignTask::Alignment).with_equalities(&equalities);
+            let aln = align_with(masked, target, &config);
+            assert_eq!(
+                validate(masked, target, &aln, &equalities),
+                Ok(()),
+                "{:?}",
+                mode
+            );
+        }
+        let aln = align(query, target, AlignMode::Infix, AlignTask::Alignment);
+        let (start, end) = aln.location().unwrap();
+        let ops = aln.operations().unwrap();
+        // Collect the bases of each side at the edits.
+        let (mut qbases, mut tbases) = (Vec::new(), Vec::new());
+        let (mut q, mut t) = (query.iter(), target[start..=end].iter());
+        for &op in ops {
+            match op {
+                0 => {
+                    q.next();
+                    t.next();
+                }
+                1 => qbases.push(*q.next().unwrap()),
+                2 => tbases.push(*t.next().unwrap()),
+                _ => {
+                    qbases.push(*q.next().unwrap());
+                    tbases.push(*t.next().unwrap());
+                }
+            }
+        }
+        assert_eq!(
+            apply_ops(&target[start..=end], ops, &qbases).unwrap(),
+            query
+        );
+        assert_eq!(
+            unapply_ops(query, ops, &tbases).unwrap(),
+            &target[start..=end]
+        );
+        assert_eq!(apply_ops(&target[start..end], ops, &qbases), None);
+        // The locations are shifted by one.
+        let wrong = align(query, &target[1..], AlignMode::Infix, AlignTask::Alignment);
+        assert!(validate(query, target, &wrong, &[]).is_err());
+        assert_eq!(
+            validate(
+                query,
+                target,
+                &align(query, target, AlignMode::Infix, AlignTask::Location),
+                &[]
+            ),
+            Err(InvalidPath::Unavailable)
+        );
+    }
+}
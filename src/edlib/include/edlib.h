@@ -29,6 +29,7 @@ extern "C" {
 // Status codes
 #define EDLIB_STATUS_OK 0
 #define EDLIB_STATUS_ERROR 1
+#define EDLIB_STATUS_CANCELLED 2
 
     /**
      * Alignment methods - how should Edlib treat gaps before and after query?
@@ -86,6 +87,11 @@ extern "C" {
 #define EDLIB_EDOP_DELETE 2   //!< Deletion from target = insertion to query.
 #define EDLIB_EDOP_MISMATCH 3 //!< Mismatch.
 
+// Strategies used to find the alignment path.
+#define EDLIB_STRATEGY_NONE 0       //!< Alignment path was not computed.
+#define EDLIB_STRATEGY_TRACEBACK 1  //!< Traceback data of whole problem was kept in memory.
+#define EDLIB_STRATEGY_HIRSCHBERG 2 //!< Problem was split into smaller ones by Hirschberg's algorithm.
+
     /**
      * @brief Defines two given characters as equal.
      */
@@ -137,11 +143,44 @@ extern "C" {
          * 0 if there are none.
          */
         int additionalEqualitiesLength;
+
+        /**
+         * Maximum size (in bytes) of data kept in memory for traceback when finding alignment path.
+         * If more is needed, Hirschberg's algorithm is used to split problem into smaller ones,
+         * which needs less memory but is slower.
+         * Set to non-positive value to use the default of 1MB.
+         */
+        long long memoryBudget;
+
+        /**
+         * Function that is called periodically during computation, with cancelData as its argument.
+         * If it returns non-zero value, computation is stopped and result has status EDLIB_STATUS_CANCELLED.
+         * Can be set to NULL if computation should never be cancelled.
+         */
+        int (*shouldCancel)(void* cancelData);
+
+        /**
+         * Data passed to shouldCancel.
+         */
+        void* cancelData;
+
+        /**
+         * Function that is called periodically during calculation of edit distance, with progressData,
+         * fraction of target columns processed (from 0 to 1) and k of current pass as its arguments.
+         * If k is auto-adjusted, target is processed again with doubled k until score is found.
+         * Can be set to NULL if progress should not be reported.
+         */
+        void (*reportProgress)(void* progressData, double progress, int k);
+
+        /**
+         * Data passed to reportProgress.
+         */
+        void* progressData;
     } EdlibAlignConfig;
 
     /**
      * Helper method for easy construction of configuration object.
-     * @return Configuration object filled with given parameters.
+     * @return Configuration object filled with given parameters, default memory budget, no cancellation and no progress reporting.
      */
     EDLIB_API EdlibAlignConfig edlibNewAlignConfig(
         int k, EdlibAlignMode mode, EdlibAlignTask task,
@@ -161,7 +200,9 @@ extern "C" {
      */
     typedef struct {
         /**
-         * EDLIB_STATUS_OK or EDLIB_STATUS_ERROR. If error, all other fields will have undefined values.
+         * EDLIB_STATUS_OK, EDLIB_STATUS_ERROR or EDLIB_STATUS_CANCELLED.
+         * If error, all other fields will have undefined values.
+         * If cancelled, edit distance is -1 and no locations or alignment are set.
          */
         int status;
 
@@ -215,6 +256,25 @@ extern "C" {
          * Number of different characters in query and target together.
          */
         int alphabetLength;
+
+        /**
+         * Strategy used to find alignment path: EDLIB_STRATEGY_TRACEBACK if traceback data of whole
+         * problem fitted into memory budget, EDLIB_STRATEGY_HIRSCHBERG if it did not,
+         * and EDLIB_STRATEGY_NONE if alignment path was not computed.
+         */
+        int alignmentStrategy;
+
+        /**
+         * k used in the last pass of edit distance calculation, which is the given k if it is non-negative,
+         * or the auto-adjusted k otherwise. -1 if there was no pass, e.g. if one of sequences is empty.
+         */
+        int finalK;
+
+        /**
+         * Number of passes of edit distance calculation. If k is auto-adjusted, it is doubled in each pass,
+         * starting from 64 (word size).
+         */
+        int numRounds;
     } EdlibAlignResult;
 
     /**
@@ -14,6 +14,10 @@ static const int WORD_SIZE = sizeof(Word) * 8; // Size of Word in bits
 static const Word WORD_1 = static_cast<Word>(1);
 static const Word HIGH_BIT_MASK = WORD_1 << (WORD_SIZE - 1);  // 100..00
 static const int MAX_UCHAR = 255;
+// By running few tests, 1MB was chosen as optimal boundary between traceback and Hirschberg's algorithm.
+static const long long DEFAULT_MEMORY_BUDGET = 1024 * 1024;
+// Number of calculated blocks between two calls of callbacks, which is around a millisecond.
+static const long long CHECKPOINT_INTERVAL = 1 << 18;
 
 // Data needed to find alignment.
 struct AlignmentData {
@@ -53,6 +57,85 @@ struct Block {
 };
 
 
+/**
+ * Calls cancellation and progress callbacks from configuration every CHECKPOINT_INTERVAL calculated blocks,
+ * and remembers if computation was cancelled.
+ * Progress is reported only during passes of edit distance calculation, started by startPass().
+ */
+class Checkpoint {
+private:
+    int (*shouldCancel)(void*);
+    void* cancelData;
+    void (*reportProgress)(void*, double, int);
+    void* progressData;
+    long long work;  // Number of blocks calculated since last call of callbacks.
+    bool cancelled;
+    int k;  // k of current pass.
+    int numColumns;  // Number of columns in current pass, 0 if there is no pass.
+    int column;  // Last column reached in current pass.
+
+    void report() {
+        if (reportProgress != NULL && numColumns > 0) {
+            reportProgress(progressData, static_cast<double>(column) / numColumns, k);
+        }
+    }
+public:
+    explicit Checkpoint(const EdlibAlignConfig& config)
+        : shouldCancel(config.shouldCancel), cancelData(config.cancelData),
+          reportProgress(config.reportProgress), progressData(config.progressData),
+          work(0), cancelled(false), k(-1), numColumns(0), column(0) {}
+
+    /**
+     * Starts pass of edit distance calculation, in which columns reached by tick() are reported as progress.
+     * @param [in] k  Maximal edit distance searched in the pass.
+     * @param [in] numColumns  Number of columns in the pass.
+     */
+    void startPass(const int k, const int numColumns) {
+        this->k = k;
+        this->numColumns = numColumns;
+        column = 0;
+    }
+
+    /**
+     * Reports that current pass is finished, and stops reporting progress until next pass.
+     */
+    void endPass() {
+        column = numColumns;
+        report();
+        numColumns = 0;
+    }
+
+    /**
+     * Calls callbacks immediately.
+     * @return True if computation was cancelled.
+     */
+    bool check() {
+        work = 0;
+        report();
+        if (!cancelled && shouldCancel != NULL) {
+            cancelled = shouldCancel(cancelData) != 0;
+        }
+        return cancelled;
+    }
+
+    /**
+     * Calls callbacks if enough blocks were calculated since last call.
+     * @param [in] numBlocks  Number of blocks calculated since last tick.
+     * @param [in] column  Column being calculated.
+     * @return True if computation was cancelled.
+     */
+    bool tick(const int numBlocks, const int column) {
+        work += numBlocks;
+        this->column = column;
+        return work >= CHECKPOINT_INTERVAL ? check() : cancelled;
+    }
+
+    bool isCancelled() const {
+        return cancelled;
+    }
+};
+
+
 /**
  * Defines equality relation on alphabet characters.
  * By default each character is always equal only to itself, but you can also provide additional equalities.
@@ -93,13 +176,13 @@ public:
 static int myersCalcEditDistanceSemiGlobal(const Word* Peq, int W, int maxNumBlocks,
                                            int queryLength,
                                            const unsigned char* target, int targetLength,
-                                           int k, EdlibAlignMode mode,
+                                           int k, EdlibAlignMode mode, Checkpoint* checkpoint,
                                            int* bestScore_, int** positions_, int* numPositions_);
 
 static int myersCalcEditDistanceNW(const Word* Peq, int W, int maxNumBlocks,
                                    int queryLength,
                                    const unsigned char* target, int targetLength,
-                                   int k, int* bestScore_,
+                                   int k, Checkpoint* checkpoint, int* bestScore_,
                                    int* position_, bool findAlignment,
                                    AlignmentData** alignData, int targetStopPosition);
 
@@ -108,13 +191,13 @@ static int obtainAlignment(
         const unsigned char* query, const unsigned char* rQuery, int queryLength,
         const unsigned char* target, const unsigned char* rTarget, int targetLength,
         const EqualityDefinition& equalityDefinition, int alphabetLength, int bestScore,
-        unsigned char** alignment, int* alignmentLength);
+        long long memoryBudget, Checkpoint* checkpoint, unsigned char** alignment, int* alignmentLength);
 
 static int obtainAlignmentHirschberg(
         const unsigned char* query, const unsigned char* rQuery, int queryLength,
         const unsigned char* target, const unsigned char* rTarget, int targetLength,
         const EqualityDefinition& equalityDefinition, int alphabetLength, int bestScore,
-        unsigned char** alignment, int* alignmentLength);
+        long long memoryBudget, Checkpoint* checkpoint, unsigned char** alignment, int* alignmentLength);
 
 static int obtainAlignmentTraceback(int queryLength, int targetLength,
                                     int bestScore, const AlignmentData* alignData,
@@ -127,6 +210,8 @@ static string transformSequences(const char* queryOriginal, int queryLength,
 
 static inline int ceilDiv(int x, int y);
 
+static inline bool useTraceback(int queryLength, int targetLength, long long memoryBudget);
+
 static inline unsigned char* createReverseCopy(const unsigned char* seq, int length);
 
 static inline Word* buildPeq(const int alphabetLength,
@@ -149,6 +234,9 @@ extern "C" EdlibAlignResult edlibAlign(const char* const queryOriginal, const in
     result.alignment = NULL;
     result.alignmentLength = 0;
     result.alphabetLength = 0;
+    result.alignmentStrategy = EDLIB_STRATEGY_NONE;
+    result.finalK = -1;
+    result.numRounds = 0;
 
     /*------------ TRANSFORM SEQUENCES AND RECOGNIZE ALPHABET -----------*/
     unsigned char* query, * target;
@@ -196,22 +284,29 @@ extern "C" EdlibAlignResult edlibAlign(const char* const queryOriginal, const in
         k = WORD_SIZE; // Gives better results than smaller k.
     }
 
+    Checkpoint checkpoint(config);
     do {
+        checkpoint.startPass(k, targetLength);
+        if (checkpoint.check()) break;
         if (config.mode == EDLIB_MODE_HW || config.mode == EDLIB_MODE_SHW) {
             myersCalcEditDistanceSemiGlobal(Peq, W, maxNumBlocks,
                                             queryLength, target, targetLength,
-                                            k, config.mode, &(result.editDistance),
+                                            k, config.mode, &checkpoint, &(result.editDistance),
                                             &(result.endLocations), &(result.numLocations));
         } else {  // mode == EDLIB_MODE_NW
             myersCalcEditDistanceNW(Peq, W, maxNumBlocks,
                                     queryLength, target, targetLength,
-                                    k, &(result.editDistance), &positionNW,
+                                    k, &checkpoint, &(result.editDistance), &positionNW,
                                     false, &alignData, -1);
         }
+        if (checkpoint.isCancelled()) break;
+        checkpoint.endPass();
+        result.finalK = k;
+        result.numRounds++;
         k *= 2;
     } while(dynamicK && result.editDistance == -1);
 
-    if (result.editDistance >= 0) {  // If there is solution.
+    if (result.editDistance >= 0 && !checkpoint.isCancelled()) {  // If there is solution.
         // If NW mode, set end location explicitly.
         if (config.mode == EDLIB_MODE_NW) {
             result.endLocations = static_cast<int *>(malloc(sizeof(int) * 1));
@@ -245,11 +340,12 @@ extern "C" EdlibAlignResult edlibAlign(const char* const queryOriginal, const in
                     } else {
                         int bestScoreSHW, numPositionsSHW;
                         int* positionsSHW;
-                        myersCalcEditDistanceSemiGlobal(
+                        int statusSHW = myersCalcEditDistanceSemiGlobal(
                                 rPeq, W, maxNumBlocks,
                                 queryLength, rTarget + targetLength - endLocation - 1, endLocation + 1,
-                                result.editDistance, EDLIB_MODE_SHW,
+                                result.editDistance, EDLIB_MODE_SHW, &checkpoint,
                                 &bestScoreSHW, &positionsSHW, &numPositionsSHW);
+                        if (statusSHW != EDLIB_STATUS_OK) break;
                         // Taking last location as start ensures that alignment will not start with insertions
                         // if it can start with mismatches instead.
                         result.startLocations[i] = endLocation - positionsSHW[numPositionsSHW - 1];
@@ -268,17 +364,20 @@ extern "C" EdlibAlignResult edlibAlign(const char* const queryOriginal, const in
 
         // Find alignment -> all comes down to finding alignment for NW.
         // Currently we return alignment only for first pair of locations.
-        if (config.task == EDLIB_TASK_PATH) {
+        if (config.task == EDLIB_TASK_PATH && !checkpoint.isCancelled()) {
             int alnStartLocation = result.startLocations[0];
             int alnEndLocation = result.endLocations[0];
             const unsigned char* alnTarget = target + alnStartLocation;
             const int alnTargetLength = alnEndLocation - alnStartLocation + 1;
             const unsigned char* rAlnTarget = createReverseCopy(alnTarget, alnTargetLength);
             const unsigned char* rQuery  = createReverseCopy(query, queryLength);
+            const long long memoryBudget = config.memoryBudget > 0 ? config.memoryBudget : DEFAULT_MEMORY_BUDGET;
             obtainAlignment(query, rQuery, queryLength,
                             alnTarget, rAlnTarget, alnTargetLength,
                             equalityDefinition, static_cast<int>(alphabet.size()), result.editDistance,
-                            &(result.alignment), &(result.alignmentLength));
+                            memoryBudget, &checkpoint, &(result.alignment), &(result.alignmentLength));
+            result.alignmentStrategy = useTraceback(queryLength, alnTargetLength, memoryBudget)
+                ? EDLIB_STRATEGY_TRACEBACK : EDLIB_STRATEGY_HIRSCHBERG;
             delete[] rAlnTarget;
             delete[] rQuery;
         }
@@ -292,6 +391,17 @@ extern "C" EdlibAlignResult edlibAlign(const char* const queryOriginal, const in
     if (alignData) delete alignData;
     //-------------------//
 
+    if (checkpoint.isCancelled()) {
+        edlibFreeAlignResult(result);
+        result.status = EDLIB_STATUS_CANCELLED;
+        result.editDistance = -1;
+        result.endLocations = result.startLocations = NULL;
+        result.numLocations = 0;
+        result.alignment = NULL;
+        result.alignmentLength = 0;
+        result.alignmentStrategy = EDLIB_STRATEGY_NONE;
+    }
+
     return result;
 }
 
@@ -451,6 +561,21 @@ static inline int ceilDiv(const int x, const int y) {
     return x % y ? x / y + 1 : x / y;
 }
 
+/**
+ * @return True if estimated memory consumption of traceback data for aligning
+ *         query and target of given lengths is smaller than memory budget (in bytes),
+ *         or if target is too short to be split by Hirschberg's algorithm.
+ */
+static inline bool useTraceback(const int queryLength, const int targetLength, const long long memoryBudget) {
+    if (targetLength <= 1) {
+        return true;
+    }
+    const long long maxNumBlocks = ceilDiv(queryLength, WORD_SIZE);
+    const long long alignmentDataSize = (2ll * sizeof(Word) + sizeof(int)) * maxNumBlocks * targetLength
+        + 2ll * sizeof(int) * targetLength;
+    return alignmentDataSize < memoryBudget;
+}
+
 static inline int min(const int x, const int y) {
     return x < y ? x : y;
 }
@@ -538,17 +663,18 @@ static inline bool allBlockCellsLarger(const Block block, const int k) {
  * @param [in] targetLength
  * @param [in] k
  * @param [in] mode  EDLIB_MODE_HW or EDLIB_MODE_SHW
+ * @param [in,out] checkpoint  Checkpoint for callbacks, ticked for each calculated column.
  * @param [out] bestScore_  Edit distance.
  * @param [out] positions_  Array of 0-indexed positions in target at which best score was found.
                             Make sure to free this array with free().
  * @param [out] numPositions_  Number of positions in the positions_ array.
- * @return Status.
+ * @return Status. EDLIB_STATUS_CANCELLED if computation was cancelled, in which case no positions are set.
  */
 static int myersCalcEditDistanceSemiGlobal(
         const Word* const Peq, const int W, const int maxNumBlocks,
         const int queryLength,
         const unsigned char* const target, const int targetLength,
-        int k, const EdlibAlignMode mode,
+        int k, const EdlibAlignMode mode, Checkpoint* const checkpoint,
         int* const bestScore_, int** const positions_, int* const numPositions_) {
     *positions_ = NULL;
     *numPositions_ = 0;
@@ -584,6 +710,11 @@ static int myersCalcEditDistanceSemiGlobal(
     const int startHout = mode == EDLIB_MODE_HW ? 0 : 1; // If 0 then gap before query is not penalized;
     const unsigned char* targetChar = target;
     for (int c = 0; c < targetLength; c++) { // for each column
+        if (checkpoint->tick(lastBlock - firstBlock + 1, c)) {
+            *bestScore_ = -1;
+            delete[] blocks;
+            return EDLIB_STATUS_CANCELLED;
+        }
         const Word* Peq_c = Peq + (*targetChar) * maxNumBlocks;
 
         //----------------------- Calculate column -------------------------//
@@ -715,6 +846,7 @@ static int myersCalcEditDistanceSemiGlobal(
  * @param [in] target
  * @param [in] targetLength
  * @param [in] k
+ * @param [in,out] checkpoint  Checkpoint for callbacks, ticked for each calculated column.
  * @param [out] bestScore_  Edit distance.
  * @param [out] position_  0-indexed position in target at which best score was found.
  * @param [in] findAlignment  If true, whole matrix is remembered and alignment data is returned.
@@ -725,12 +857,12 @@ static int myersCalcEditDistanceSemiGlobal(
  * @param [out] targetStopPosition  If set to -1, whole calculation is performed normally, as expected.
  *         If set to p, calculation is performed up to position p in target (inclusive)
  *         and column p is returned as the only column in alignData.
- * @return Status.
+ * @return Status. EDLIB_STATUS_CANCELLED if computation was cancelled, in which case no score is set.
  */
 static int myersCalcEditDistanceNW(const Word* const Peq, const int W, const int maxNumBlocks,
                                    const int queryLength,
                                    const unsigned char* const target, const int targetLength,
-                                   int k, int* const bestScore_,
+                                   int k, Checkpoint* const checkpoint, int* const bestScore_,
                                    int* const position_, const bool findAlignment,
                                    AlignmentData** const alignData, const int targetStopPosition) {
     if (targetStopPosition > -1 && findAlignment) {
@@ -776,6 +908,11 @@ static int myersCalcEditDistanceNW(const Word* const Peq, const int W, const int
 
     const unsigned char* targetChar = target;
     for (int c = 0; c < targetLength; c++) { // for each column
+        if (checkpoint->tick(lastBlock - firstBlock + 1, c)) {
+            *bestScore_ = *position_ = -1;
+            delete[] blocks;
+            return EDLIB_STATUS_CANCELLED;
+        }
         const Word* Peq_c = Peq + *targetChar * maxNumBlocks;
 
         //----------------------- Calculate column -------------------------//
@@ -1157,6 +1294,8 @@ static int obtainAlignmentTraceback(const int queryLength, const int targetLengt
  * @param [in] equalityDefinition
  * @param [in] alphabetLength
  * @param [in] bestScore  Best(optimal) score.
+ * @param [in] memoryBudget  Maximum size (in bytes) of traceback data, above which problem is split.
+ * @param [in,out] checkpoint  Checkpoint for callbacks.
  * @param [out] alignment  Sequence of edit operations that make target equal to query.
  * @param [out] alignmentLength  Length of alignment.
  * @return Status code.
@@ -1165,6 +1304,7 @@ static int obtainAlignment(
         const unsigned char* const query, const unsigned char* const rQuery, const int queryLength,
         const unsigned char* const target, const unsigned char* const rTarget, const int targetLength,
         const EqualityDefinition& equalityDefinition, const int alphabetLength, const int bestScore,
+        const long long memoryBudget, Checkpoint* const checkpoint,
         unsigned char** const alignment, int* const alignmentLength) {
 
     // Handle special case when one of sequences has length of 0.
@@ -1186,31 +1326,31 @@ static int obtainAlignment(
     // and it could also be done for alignments - we could have one big array for alignment that would be
     // sparsely populated by each of steps in recursion, and at the end we would just consolidate those results.
 
-    // If estimated memory consumption for traceback algorithm is smaller than 1MB use it,
-    // otherwise use Hirschberg's algorithm. By running few tests I choose boundary of 1MB as optimal.
-    long long alignmentDataSize = (2ll * sizeof(Word) + sizeof(int)) * maxNumBlocks * targetLength
-        + 2ll * sizeof(int) * targetLength;
-    if (alignmentDataSize < 1024 * 1024) {
+    // If estimated memory consumption for traceback algorithm is within memory budget use it,
+    // otherwise use Hirschberg's algorithm.
+    if (useTraceback(queryLength, targetLength, memoryBudget)) {
         int score_, endLocation_;  // Used only to call function.
         AlignmentData* alignData = NULL;
         Word* Peq = buildPeq(alphabetLength, query, queryLength, equalityDefinition);
-        myersCalcEditDistanceNW(Peq, W, maxNumBlocks,
-                                queryLength,
-                                target, targetLength,
-                                bestScore,
-                                &score_, &endLocation_, true, &alignData, -1);
+        statusCode = myersCalcEditDistanceNW(Peq, W, maxNumBlocks,
+                                             queryLength,
+                                             target, targetLength,
+                                             bestScore, checkpoint,
+                                             &score_, &endLocation_, true, &alignData, -1);
         //assert(score_ == bestScore);
         //assert(endLocation_ == targetLength - 1);
 
-        statusCode = obtainAlignmentTraceback(queryLength, targetLength,
-                                              bestScore, alignData, alignment, alignmentLength);
+        if (statusCode == EDLIB_STATUS_OK) {
+            statusCode = obtainAlignmentTraceback(queryLength, targetLength,
+                                                  bestScore, alignData, alignment, alignmentLength);
+        }
         delete alignData;
         delete[] Peq;
     } else {
         statusCode = obtainAlignmentHirschberg(query, rQuery, queryLength,
                                                target, rTarget, targetLength,
                                                equalityDefinition, alphabetLength, bestScore,
-                                               alignment, alignmentLength);
+                                               memoryBudget, checkpoint, alignment, alignmentLength);
     }
     return statusCode;
 }
@@ -1227,6 +1367,8 @@ static int obtainAlignment(
  * @param [in] targetLength
  * @param [in] alphabetLength
  * @param [in] bestScore  Best(optimal) score.
+ * @param [in] memoryBudget  Maximum size (in bytes) of traceback data, above which problem is split.
+ * @param [in,out] checkpoint  Checkpoint for callbacks.
  * @param [out] alignment  Sequence of edit operations that make target equal to query.
  * @param [out] alignmentLength  Length of alignment.
  * @return Status code.
@@ -1235,6 +1377,7 @@ static int obtainAlignmentHirschberg(
         const unsigned char* const query, const unsigned char* const rQuery, const int queryLength,
         const unsigned char* const target, const unsigned char* const rTarget, const int targetLength,
         const EqualityDefinition& equalityDefinition, const int alphabetLength, const int bestScore,
+        const long long memoryBudget, Checkpoint* const checkpoint,
         unsigned char** const alignment, int* const alignmentLength) {
 
     const int maxNumBlocks = ceilDiv(queryLength, WORD_SIZE);
@@ -1253,22 +1396,22 @@ static int obtainAlignmentHirschberg(
     // Calculate left half.
     AlignmentData* alignDataLeftHalf = NULL;
     int leftHalfCalcStatus = myersCalcEditDistanceNW(
-            Peq, W, maxNumBlocks, queryLength, target, targetLength, bestScore,
+            Peq, W, maxNumBlocks, queryLength, target, targetLength, bestScore, checkpoint,
             &score_, &endLocation_, false, &alignDataLeftHalf, leftHalfWidth - 1);
 
     // Calculate right half.
     AlignmentData* alignDataRightHalf = NULL;
     int rightHalfCalcStatus = myersCalcEditDistanceNW(
-            rPeq, W, maxNumBlocks, queryLength, rTarget, targetLength, bestScore,
+            rPeq, W, maxNumBlocks, queryLength, rTarget, targetLength, bestScore, checkpoint,
             &score_, &endLocation_, false, &alignDataRightHalf, rightHalfWidth - 1);
 
     delete[] Peq;
     delete[] rPeq;
 
-    if (leftHalfCalcStatus == EDLIB_STATUS_ERROR || rightHalfCalcStatus == EDLIB_STATUS_ERROR) {
+    if (leftHalfCalcStatus != EDLIB_STATUS_OK || rightHalfCalcStatus != EDLIB_STATUS_OK) {
         if (alignDataLeftHalf) delete alignDataLeftHalf;
         if (alignDataRightHalf) delete alignDataRightHalf;
-        return EDLIB_STATUS_ERROR;
+        return leftHalfCalcStatus != EDLIB_STATUS_OK ? leftHalfCalcStatus : rightHalfCalcStatus;
     }
 
     // Unwrap the left half.
@@ -1375,16 +1518,16 @@ static int obtainAlignmentHirschberg(
     int ulStatusCode = obtainAlignment(query, rQuery + lrHeight, ulHeight,
                                        target, rTarget + lrWidth, ulWidth,
                                        equalityDefinition, alphabetLength, leftScore,
-                                       &ulAlignment, &ulAlignmentLength);
+                                       memoryBudget, checkpoint, &ulAlignment, &ulAlignmentLength);
     unsigned char* lrAlignment = NULL; int lrAlignmentLength;
     int lrStatusCode = obtainAlignment(query + ulHeight, rQuery, lrHeight,
                                        target + ulWidth, rTarget, lrWidth,
                                        equalityDefinition, alphabetLength, rightScore,
-                                       &lrAlignment, &lrAlignmentLength);
-    if (ulStatusCode == EDLIB_STATUS_ERROR || lrStatusCode == EDLIB_STATUS_ERROR) {
+                                       memoryBudget, checkpoint, &lrAlignment, &lrAlignmentLength);
+    if (ulStatusCode != EDLIB_STATUS_OK || lrStatusCode != EDLIB_STATUS_OK) {
         if (ulAlignment) free(ulAlignment);
         if (lrAlignment) free(lrAlignment);
-        return EDLIB_STATUS_ERROR;
+        return ulStatusCode != EDLIB_STATUS_OK ? ulStatusCode : lrStatusCode;
     }
 
     // Build alignment by concatenating upper left alignment with lower right alignment.
@@ -1468,6 +1611,11 @@ extern "C" EdlibAlignConfig edlibNewAlignConfig(int k, EdlibAlignMode mode, Edli
     config.task = task;
     config.additionalEqualities = additionalEqualities;
     config.additionalEqualitiesLength = additionalEqualitiesLength;
+    config.memoryBudget = -1;
+    config.shouldCancel = NULL;
+    config.cancelData = NULL;
+    config.reportProgress = NULL;
+    config.progressData = NULL;
     return config;
 }
 
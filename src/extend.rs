@@ -0,0 +1,292 @@
+//! # Extension from a seed
+//!
+//! Seed-and-extend mappers extend a seed hit to the left and to the right, anchoring only the
+//! end at the seed. This module aligns the sequences on each side of the anchor by edlib's prefix mode
+//! (`EDLIB_MODE_SHW`, on the reversed sequences for the left side) chunk by chunk until the alignment drops
+//! (see [`Extender::with_x_drop`]), and then trims the alignment at the furthest point where the number of edits
+//! per aligned query base is at most the given threshold.
+//!
+//! The extension is a heuristic, not an optimal one: each chunk is aligned optimally on its own, from where the
+//! previous chunk ended, so the results depend on the chunk boundaries, and the extension may stop at a drop
+//! that a longer alignment would have recovered from.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::extend::extend;
+//! // The seed "GGGG" is at 4 on the query and 6 on the target.
+//! let query =  b"ACGTGGGGTTAGCCCCCCCC";
+//! let target = b"TTACGTGGGGTTACGAAAAAAAA";
+//! let (left, right) = extend(query, target, (4, 6), 0.2);
+//! assert_eq!((left.query_len(), left.target_len(), left.dist()), (4, 4, 0));
+//! assert_eq!((right.query_len(), right.target_len(), right.dist()), (9, 8, 1));
+//! ```
+use crate::{align_uncancellable, AlignConfig, AlignMode, AlignTask};
+
+/// An extension from an anchor, to the left or to the right.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Extension {
+    query_len: usize,
+    target_len: usize,
+    dist: u32,
+    operations: Vec<u8>,
+}
+
+impl Extension {
+    /// Return the length of the extension on the query.
+    pub fn query_len(&self) -> usize {
+        self.query_len
+    }
+    /// Return the length of the extension on the target.
+    pub fn target_len(&self) -> usize {
+        self.target_len
+    }
+    /// Return the number of the edits in the extension.
+    pub fn dist(&self) -> u32 {
+        self.dist
+    }
+    /// Return the alignment operations of the extension, from left to right
+    /// (see [`crate::Alignment::operations()`] for the encoding).
+    pub fn operations(&self) -> &[u8] {
+        &self.operations
+    }
+}
+
+// The number of query bases aligned at once.
+const CHUNK: usize = 256;
+
+/// The extension of anchors under an error rate.
+/// # Example
+/// ```
+/// use edlib_sys::extend::Extender;
+/// let extender = Extender::new(0.2).with_x_drop(5.0);
+/// let (left, right) = extender.extend(b"ACGTGGGG", b"ACGTGGGG", (4, 4));
+/// assert_eq!((left.query_len(), right.query_len()), (4, 4));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Extender {
+    max_error_rate: f64,
+    x_drop: f64,
+}
+
+impl Extender {
+    /// Create an extender stopping at the furthest point where the edits per aligned query base
+    /// do not exceed `max_error_rate`. The X-drop is 20 by default.
+    pub fn new(max_error_rate: f64) -> Self {
+        Self {
+            max_error_rate,
+            x_drop: 20.0,
+        }
+    }
+    /// Stop the extension once the score, `max_error_rate` per aligned query base minus one per edit,
+    /// falls more than `x_drop` below its best, i.e., once the extension has `x_drop` more edits than allowed
+    /// by the error rate since its best point. A larger X-drop extends through longer divergent regions,
+    /// at the cost of aligning further into unrelated sequences.
+    pub fn with_x_drop(mut self, x_drop: f64) -> Self {
+        self.x_drop = x_drop;
+        self
+    }
+    /// Return the maximum edits per aligned query base.
+    pub fn max_error_rate(&self) -> f64 {
+        self.max_error_rate
+    }
+    /// Return the X-drop. See [`Extender::with_x_drop`].
+    pub fn x_drop(&self) -> f64 {
+        self.x_drop
+    }
+    /// Extend an anchor at `(query position, target position)` to both sides. See [`extend`].
+    /// # Panics
+    /// Panics if the anchor is out of the query or the target.
+    pub fn extend(
+        &self,
+        query: &[u8],
+        target: &[u8],
+        anchor: (usize, usize),
+    ) -> (Extension, Extension) {
+        let (qpos, tpos) = anchor;
+        assert!(
+            qpos <= query.len() && tpos <= target.len(),
+            "anchor {:?} is out of the sequences of lengths ({}, {})",
+            anchor,
+            query.len(),
+            target.len()
+        );
+        let left = self.extend_left(&query[..qpos], &target[..tpos]);
+        let right = self.extend_right(&query[qpos..], &target[tpos..]);
+        (left, right)
+    }
+    /// Extend from the start of both sequences to the right.
+    /// The query is aligned chunk by chunk, and the extension stops at the X-drop (see [`Extender::with_x_drop`]).
+    /// Thus it takes time proportional to the extension, not to the whole sequences.
+    pub fn extend_right(&self, query: &[u8], target: &[u8]) -> Extension {
+        extend_chunks(query, target, self.max_error_rate, self.x_drop)
+    }
+    /// Extend from the end of both sequences to the left.
+    pub fn extend_left(&self, query: &[u8], target: &[u8]) -> Extension {
+        let query: Vec<_> = query.iter().rev().copied().collect();
+        let target: Vec<_> = target.iter().rev().copied().collect();
+        let mut extension = self.extend_right(&query, &target);
+        extension.operations.reverse();
+        extension
+    }
+}
+
+/// Extend an anchor at `(query position, target position)` to both sides, with the default X-drop.
+/// Return the extensions to the left (ending just before the anchor) and to the right (starting at the anchor).
+/// Each extension stops at the furthest point where the edits per aligned query base do not exceed `max_error_rate`.
+/// # Panics
+/// Panics if the anchor is out of the query or the target.
+pub fn extend(
+    query: &[u8],
+    target: &[u8],
+    anchor: (usize, usize),
+    max_error_rate: f64,
+) -> (Extension, Extension) {
+    Extender::new(max_error_rate).extend(query, target, anchor)
+}
+
+/// Extend from the start of both sequences to the right, with the default X-drop. See [`Extender::extend_right`].
+pub fn extend_right(query: &[u8], target: &[u8], max_error_rate: f64) -> Extension {
+    Extender::new(max_error_rate).extend_right(query, target)
+}
+
+/// Extend from the end of both sequences to the left, with the default X-drop. See [`Extender::extend_left`].
+pub fn extend_left(query: &[u8], target: &[u8], max_error_rate: f64) -> Extension {
+    Extender::new(max_error_rate).extend_left(query, target)
+}
+
+// Align the query chunk by chunk from the start of both sequences, until the X-drop.
+fn extend_chunks(query: &[u8], target: &[u8], max_error_rate: f64, x_drop: f64) -> Extension {
+    let config = AlignConfig::new(AlignMode::Prefix, AlignTask::Alignment);
+    let mut operations = Vec::new();
+    let (mut qlen, mut tlen, mut dist) = (0, 0, 0);
+    // The furthest match such that the alignment up to it has few edits: (ops, query, target, dist).
+    let mut best = (0, 0, 0, 0);
+    let mut best_score = 0.0f64;
+    'extend: while qlen < query.len() && tlen < target.len() {
+        let chunk = &query[qlen..query.len().min(qlen + CHUNK)];
+        // The chunk can not be aligned to more than this on the target.
+        let max_dist = (chunk.len() as f64 * max_error_rate).ceil() as usize;
+        let window = &target[tlen..target.len().min(tlen + chunk.len() + max_dist + 1)];
+        let aln = align_uncancellable(chunk, window, &config);
+        for &op in aln.operations.iter() {
+            operations.push(op);
+            qlen += (op != 2) as usize;
+            tlen += (op != 1) as usize;
+            dist += (op != 0) as u32;
+            let score = max_error_rate * qlen as f64 - dist as f64;
+            if op == 0 && 0.0 <= score {
+                best = (operations.len(), qlen, tlen, dist);
+            }
+            best_score = best_score.max(score);
+            if score < best_score - x_drop {
+                break 'extend;
+            }
+        }
+    }
+    let (len, query_len, target_len, dist) = best;
+    operations.truncate(len);
+    Extension {
+        query_len,
+        target_len,
+        dist,
+        operations,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn extend_test() {
+        // An insertion on the left, and a random tail on the right.
+        let query = b"CCCCACGTTACGTAAAATTTTTTTT";
+        let target = b"GGGGACGTACGTAAAACGCGCGCGCG";
+        let (left, right) = extend(query, target, (12, 11), 0.2);
+        assert_eq!(
+            (left.query_len(), left.target_len(), left.dist()),
+            (8, 7, 1)
+        );
+        assert_eq!(left.operations().len(), 8);
+        assert_eq!(right.query_len(), 5);
+        assert_eq!(right.target_len(), 5);
+        assert_eq!(right.operations(), [0, 0, 0, 0, 0].as_slice());
+        let (left, right) = extend(query, target, (0, 0), 0.2);
+        assert_eq!(left.query_len(), 0);
+        assert_eq!(right.dist(), 0);
+    }
+    #[test]
+    #[should_panic]
+    fn anchor_test() {
+        extend(b"ACGT", b"ACGT", (5, 0), 0.2);
+    }
+    #[test]
+    fn chunk_test() {
+        let mut x = 7u64;
+        let mut random = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    b"ACGT"[(x >> 62) as usize]
+                })
+                .collect()
+        };
+        // A similar region over several chunks, followed by a long unrelated tail.
+        let seed = random(1000);
+        let mut query = seed.clone();
+        for i in (50..query.len()).step_by(50) {
+            query[i] = if query[i] == b'A' { b'C' } else { b'A' };
+        }
+        query.extend(random(200_000));
+        let target = [seed, random(200_000)].concat();
+        let right = extend_right(&query, &target, 0.1);
+        // The extension runs a little into the tail, as far as the error rate allows, and stops soon after.
+        assert!((1000..1200).contains(&right.query_len()));
+        assert!(right.dist() as f64 <= 0.1 * right.query_len() as f64);
+        let ops = right.operations();
+        let (mut qpos, mut tpos, mut dist) = (0, 0, 0);
+        for &op in ops {
+            if op == 0 || op == 3 {
+                assert_eq!(query[qpos] == target[tpos], op == 0);
+            }
+            qpos += (op != 2) as usize;
+            tpos += (op != 1) as usize;
+            dist += (op != 0) as u32;
+        }
+        assert_eq!(
+            (qpos, tpos, dist),
+            (right.query_len(), right.target_len(), right.dist())
+        );
+        // The mismatches of the similar region.
+        assert!(ops[..1000]
+            .iter()
+            .enumerate()
+            .all(|(i, &op)| (op == 3) == (i != 0 && i % 50 == 0)));
+        // The left extension of the reversed sequences is the mirror image.
+        let (query, target): (Vec<_>, Vec<_>) = (
+            query.into_iter().rev().collect(),
+            target.into_iter().rev().collect(),
+        );
+        let left = extend_left(&query, &target, 0.1);
+        assert_eq!(
+            (left.query_len(), left.target_len()),
+            (right.query_len(), right.target_len())
+        );
+        assert!(left.operations().iter().eq(right.operations().iter().rev()));
+    }
+    #[test]
+    fn x_drop_test() {
+        // A divergent region of 16 mismatches between two identical regions.
+        let query = [&[b'A'; 40][..], &[b'C'; 16], &[b'G'; 40]].concat();
+        let target = [&[b'A'; 40][..], &[b'T'; 16], &[b'G'; 40]].concat();
+        // The default X-drop extends through the region.
+        let right = extend_right(&query, &target, 0.2);
+        assert_eq!((right.query_len(), right.dist()), (96, 16));
+        // A small X-drop stops in the region, and trims the extension before it.
+        let extender = Extender::new(0.2).with_x_drop(2.0);
+        assert_eq!(extender.x_drop(), 2.0);
+        let right = extender.extend_right(&query, &target);
+        assert_eq!((right.query_len(), right.dist()), (40, 0));
+    }
+}
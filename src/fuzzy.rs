@@ -0,0 +1,210 @@
+//! # Fuzzy matching
+//!
+//! Shortcuts for comparing short strings, such as names or identifiers, by the Levenshtein distance:
+//!
+//! - [`levenshtein`]: The edit distance between two strings.
+//! - [`levenshtein_within`]: The edit distance if it is at most a threshold, which is cheaper for distant strings.
+//! - [`normalized_similarity`]: The edit distance scaled into `[0, 1]`, where 1 means identical.
+//! - [`best_match`]: The candidates within a distance from the query, ranked from the closest.
+//!
+//! The strings are compared by characters, so that a multi-byte character counts as a single edit.
+//! The distances are computed in the [`AlignMode::Global`] mode with [`AlignTask::Distance`],
+//! where the threshold is given as `k`. ASCII strings are aligned by their bytes directly, as [`crate::align_with`]
+//! does, and the others are encoded by [`align_str`] first. Strings with more than 256 distinct characters together
+//! are compared by a plain dynamic programming instead.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::fuzzy::{best_match, levenshtein, normalized_similarity};
+//! assert_eq!(levenshtein("kitten", "sitting"), 3);
+//! assert_eq!(levenshtein("Müller", "Muller"), 1);
+//! assert_eq!(normalized_similarity("kitten", "sitting"), 1.0 - 3.0 / 7.0);
+//! let names = ["Jonathan", "Johnathan", "Jon", "Jonathon"];
+//! let matches = best_match("Jonathan", &names, 1);
+//! let ranked: Vec<_> = matches.iter().map(|m| (m.index(), m.dist())).collect();
+//! assert_eq!(ranked, [(0, 0), (1, 1), (3, 1)]);
+//! ```
+use crate::text::{align_str, Granularity};
+use crate::{align_uncancellable, AlignConfig, AlignMode, AlignTask};
+
+/// A candidate within the distance from the query.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct FuzzyMatch {
+    index: usize,
+    dist: u32,
+    similarity: f64,
+}
+
+impl FuzzyMatch {
+    /// Return the index of the candidate.
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    /// Return the edit distance between the query and the candidate.
+    pub fn dist(&self) -> u32 {
+        self.dist
+    }
+    /// Return the normalized similarity between the query and the candidate.
+    pub fn similarity(&self) -> f64 {
+        self.similarity
+    }
+}
+
+// The Levenshtein distance between the characters of `a` and `b`, by the textbook dynamic programming.
+fn naive(a: &str, b: &str) -> u32 {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<u32> = (0..=b.len() as u32).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut next = vec![i as u32 + 1; b.len() + 1];
+        for (j, &y) in b.iter().enumerate() {
+            next[j + 1] = (row[j] + (x != y) as u32)
+                .min(row[j + 1] + 1)
+                .min(next[j] + 1);
+        }
+        row = next;
+    }
+    row[b.len()]
+}
+
+// Return the distance between `a` and `b` if it is at most `max_dist`, if any.
+fn distance(a: &str, b: &str, max_dist: Option<u32>) -> Option<u32> {
+    // The bytes of ASCII strings are their characters, which need no encoding.
+    let ascii = a.is_ascii() && b.is_ascii();
+    let len = |s: &str| match ascii {
+        true => s.len(),
+        false => s.chars().count(),
+    };
+    let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance);
+    let config = match max_dist {
+        Some(max_dist) => {
+            // The distance is at least the difference of the lengths.
+            if (max_dist as usize) < len(a).abs_diff(len(b)) {
+                return None;
+            }
+            config.with_k(max_dist)
+        }
+        None => config,
+    };
+    if ascii {
+        let aln = align_uncancellable(a.as_bytes(), b.as_bytes(), &config);
+        return aln.is_found().then(|| aln.dist());
+    }
+    match align_str(a, b, Granularity::Chars, &config) {
+        Ok(aln) => aln.alignment().is_found().then(|| aln.dist()),
+        // Too many distinct characters for edlib.
+        Err(_) => Some(naive(a, b)).filter(|&dist| max_dist.is_none_or(|k| dist <= k)),
+    }
+}
+
+// Scale the distance by the length of the longer string. Two empty strings are identical.
+fn similarity(dist: u32, a: &str, b: &str) -> f64 {
+    match a.chars().count().max(b.chars().count()) {
+        0 => 1.0,
+        len => 1.0 - dist as f64 / len as f64,
+    }
+}
+
+/// Return the Levenshtein distance between `a` and `b`, in characters.
+pub fn levenshtein(a: &str, b: &str) -> u32 {
+    distance(a, b, None).unwrap()
+}
+
+/// Return the Levenshtein distance between `a` and `b` if it is at most `max_dist`, otherwise None.
+/// It stops early for distant strings, unlike [`levenshtein`].
+pub fn levenshtein_within(a: &str, b: &str, max_dist: u32) -> Option<u32> {
+    distance(a, b, Some(max_dist))
+}
+
+/// Return `1 - levenshtein(a, b) / max(len(a), len(b))` in characters, or 1 if both are empty.
+pub fn normalized_similarity(a: &str, b: &str) -> f64 {
+    similarity(levenshtein(a, b), a, b)
+}
+
+/// Return the candidates at most `max_dist` away from the `query`, in the increasing order of the distance.
+/// Ties are broken by the index of the candidate.
+pub fn best_match<S: AsRef<str>>(query: &str, candidates: &[S], max_dist: u32) -> Vec<FuzzyMatch> {
+    let mut matches: Vec<_> = candidates
+        .iter()
+        .enumerate()
+        .filter_map(|(index, candidate)| {
+            let candidate = candidate.as_ref();
+            let dist = levenshtein_within(query, candidate, max_dist)?;
+            Some(FuzzyMatch {
+                index,
+                dist,
+                similarity: similarity(dist, query, candidate),
+            })
+        })
+        .collect();
+    matches.sort_by_key(|m| (m.dist, m.index));
+    matches
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn fuzzy_test() {
+        assert_eq!(levenshtein("", ""), 0);
+        assert_eq!(levenshtein("", "abc"), 3);
+        assert_eq!(levenshtein("flaw", "lawn"), 2);
+        // Multi-byte characters count as single edits.
+        assert_eq!(levenshtein("東京都", "京都"), 1);
+        assert_eq!(levenshtein_within("flaw", "lawn", 1), None);
+        assert_eq!(levenshtein_within("flaw", "lawn", 2), Some(2));
+        assert_eq!(normalized_similarity("", ""), 1.0);
+        assert_eq!(normalized_similarity("abcd", "abcd"), 1.0);
+        assert_eq!(normalized_similarity("abcd", "wxyz"), 0.0);
+        assert_eq!(normalized_similarity("abcd", "abc"), 0.75);
+        assert_eq!(normalized_similarity("Müll", "Mull"), 0.75);
+        let candidates = vec![
+            "smyth".to_string(),
+            "smith".to_string(),
+            "smithson".to_string(),
+            "smit".to_string(),
+        ];
+        let matches = best_match("smith", &candidates, 1);
+        assert_eq!(matches.len(), 3);
+        assert_eq!((matches[0].index(), matches[0].dist()), (1, 0));
+        assert_eq!(matches[0].similarity(), 1.0);
+        assert_eq!((matches[1].index(), matches[1].dist()), (0, 1));
+        assert_eq!((matches[2].index(), matches[2].dist()), (3, 1));
+        assert_eq!(matches[2].similarity(), 0.8);
+        assert!(best_match("smith", &candidates[2..3], 2).is_empty());
+        assert!(best_match::<&str>("smith", &[], 2).is_empty());
+    }
+    #[test]
+    fn ascii_test() {
+        // The same distances with and without the fast path for ASCII strings.
+        let mut x = 48u64;
+        let mut random = |len: usize| -> String {
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    "abcé"[(x >> 62) as usize..].chars().next().unwrap()
+                })
+                .collect()
+        };
+        for _ in 0..50 {
+            let (a, b) = (random(12), random(10));
+            let (ascii_a, ascii_b) = (a.replace('é', "e"), b.replace('é', "e"));
+            assert_eq!(levenshtein(&a, &b), naive(&a, &b));
+            assert_eq!(levenshtein(&ascii_a, &ascii_b), naive(&ascii_a, &ascii_b));
+            for max_dist in 0..6 {
+                let expected = Some(naive(&ascii_a, &ascii_b)).filter(|&d| d <= max_dist);
+                assert_eq!(levenshtein_within(&ascii_a, &ascii_b, max_dist), expected);
+            }
+        }
+    }
+    #[test]
+    fn many_chars_test() {
+        // More than 256 distinct characters in total.
+        let a: String = ('\u{4e00}'..'\u{4f00}').collect();
+        let b: String = a.chars().skip(1).chain('\u{5000}'..'\u{5002}').collect();
+        assert_eq!(levenshtein(&a, &b), 3);
+        assert_eq!(levenshtein_within(&a, &b, 2), None);
+        assert_eq!(best_match(&a, &[&b], 3)[0].dist(), 3);
+    }
+}
@@ -0,0 +1,481 @@
+//! A dependency-free gzip decoder (RFC 1951 and RFC 1952) for the [`crate::io`] module.
+//!
+//! The decoder streams: it keeps the last 32 KB of the output as the window of the back-references,
+//! and decodes at most about [`WINDOW`] bytes at a time. Concatenated gzip members are decoded one after another,
+//! as `gzip -d` does, and the CRC-32 and the size of each member are checked.
+use std::io::{BufRead, Error, ErrorKind, Read, Result};
+
+/// The magic bytes at the start of a gzip member.
+pub(crate) const MAGIC: [u8; 2] = [0x1f, 0x8b];
+// The maximum distance of a back-reference.
+const WINDOW: usize = 1 << 15;
+const MAX_BITS: usize = 15;
+
+// The base lengths and the extra bits of the length codes 257..=285.
+const LENGTH_BASE: [u16; 29] = [
+    3, 4, 5, 6, 7, 8, 9, 10, 11, 13, 15, 17, 19, 23, 27, 31, 35, 43, 51, 59, 67, 83, 99, 115, 131,
+    163, 195, 227, 258,
+];
+const LENGTH_EXTRA: [u8; 29] = [
+    0, 0, 0, 0, 0, 0, 0, 0, 1, 1, 1, 1, 2, 2, 2, 2, 3, 3, 3, 3, 4, 4, 4, 4, 5, 5, 5, 5, 0,
+];
+// The base distances and the extra bits of the distance codes 0..=29.
+const DIST_BASE: [u16; 30] = [
+    1, 2, 3, 4, 5, 7, 9, 13, 17, 25, 33, 49, 65, 97, 129, 193, 257, 385, 513, 769, 1025, 1537,
+    2049, 3073, 4097, 6145, 8193, 12289, 16385, 24577,
+];
+const DIST_EXTRA: [u8; 30] = [
+    0, 0, 0, 0, 1, 1, 2, 2, 3, 3, 4, 4, 5, 5, 6, 6, 7, 7, 8, 8, 9, 9, 10, 10, 11, 11, 12, 12, 13,
+    13,
+];
+// The order of the code lengths of the code length alphabet in a dynamic block.
+const CODE_LENGTH_ORDER: [usize; 19] = [
+    16, 17, 18, 0, 8, 7, 9, 6, 10, 5, 11, 4, 12, 3, 13, 2, 14, 1, 15,
+];
+
+fn invalid_data(msg: &str) -> Error {
+    Error::new(
+        ErrorKind::InvalidData,
+        format!("invalid gzip data: {}", msg),
+    )
+}
+
+// The table of the CRC-32 of the gzip trailer, computed byte by byte.
+const CRC_TABLE: [u32; 256] = crc_table();
+
+const fn crc_table() -> [u32; 256] {
+    let mut table = [0u32; 256];
+    let mut n = 0;
+    while n < 256 {
+        let mut c = n as u32;
+        let mut k = 0;
+        while k < 8 {
+            c = match c & 1 {
+                1 => 0xedb8_8320 ^ (c >> 1),
+                _ => c >> 1,
+            };
+            k += 1;
+        }
+        table[n] = c;
+        n += 1;
+    }
+    table
+}
+
+fn crc32(crc: u32, bytes: &[u8]) -> u32 {
+    !bytes.iter().fold(!crc, |c, &b| {
+        CRC_TABLE[((c ^ b as u32) & 0xff) as usize] ^ (c >> 8)
+    })
+}
+
+// A canonical Huffman code: the number of codes of each length, and the symbols ordered by their codes.
+#[derive(Debug, Clone)]
+struct Huffman {
+    counts: [u16; MAX_BITS + 1],
+    symbols: Vec<u16>,
+}
+
+impl Huffman {
+    // Build the code from the code lengths of the symbols, where zero means an unused symbol.
+    // Incomplete codes are allowed, as zlib writes them for a single distance code.
+    fn new(lengths: &[u8]) -> Result<Self> {
+        let mut counts = [0u16; MAX_BITS + 1];
+        for &len in lengths {
+            counts[len as usize] += 1;
+        }
+        counts[0] = 0;
+        let mut left = 1i32;
+        for &count in &counts[1..] {
+            left = (left << 1) - count as i32;
+            if left < 0 {
+                return Err(invalid_data("over-subscribed Huffman code"));
+            }
+        }
+        let mut offsets = [0u16; MAX_BITS + 2];
+        for len in 1..=MAX_BITS {
+            offsets[len + 1] = offsets[len] + counts[len];
+        }
+        let mut symbols = vec![0; offsets[MAX_BITS + 1] as usize];
+        for (symbol, &len) in lengths.iter().enumerate().filter(|(_, &len)| len != 0) {
+            symbols[offsets[len as usize] as usize] = symbol as u16;
+            offsets[len as usize] += 1;
+        }
+        Ok(Self { counts, symbols })
+    }
+    fn fixed() -> (Self, Self) {
+        let mut lengths = [8u8; 288];
+        lengths[144..256].fill(9);
+        lengths[256..280].fill(7);
+        let literals = Self::new(&lengths).unwrap();
+        let distances = Self::new(&[5; 30]).unwrap();
+        (literals, distances)
+    }
+}
+
+// A reader of the bits of a deflate stream, from the least significant bit of each byte.
+// Bytes are read only when their bits are needed, so that nothing is read beyond the end of a member.
+struct BitReader<R: BufRead> {
+    reader: R,
+    bits: u32,
+    count: u32,
+}
+
+impl<R: BufRead> BitReader<R> {
+    fn byte(&mut self) -> Result<u8> {
+        let byte = match self.reader.fill_buf()?.first() {
+            Some(&byte) => byte,
+            None => {
+                let msg = "unexpected end of gzip data";
+                return Err(Error::new(ErrorKind::UnexpectedEof, msg));
+            }
+        };
+        self.reader.consume(1);
+        Ok(byte)
+    }
+    fn bits(&mut self, n: u32) -> Result<u32> {
+        while self.count < n {
+            self.bits |= (self.byte()? as u32) << self.count;
+            self.count += 8;
+        }
+        let value = self.bits & ((1u64 << n) - 1) as u32;
+        self.bits >>= n;
+        self.count -= n;
+        Ok(value)
+    }
+    // Discard the rest of the current byte.
+    fn align(&mut self) {
+        self.bits = 0;
+        self.count = 0;
+    }
+    fn u16_le(&mut self) -> Result<u16> {
+        Ok(self.bits(16)? as u16)
+    }
+    fn u32_le(&mut self) -> Result<u32> {
+        Ok(self.bits(16)? | (self.bits(16)? << 16))
+    }
+    fn is_eof(&mut self) -> Result<bool> {
+        Ok(self.count == 0 && self.reader.fill_buf()?.is_empty())
+    }
+    // Decode a symbol bit by bit, comparing the code with the first code of each length.
+    fn decode(&mut self, code: &Huffman) -> Result<u16> {
+        let (mut value, mut first, mut index) = (0i32, 0i32, 0i32);
+        for &count in &code.counts[1..] {
+            value |= self.bits(1)? as i32;
+            let count = count as i32;
+            if value - first < count {
+                return Ok(code.symbols[(index + value - first) as usize]);
+            }
+            index += count;
+            first = (first + count) << 1;
+            value <<= 1;
+        }
+        Err(invalid_data("invalid Huffman code"))
+    }
+}
+
+// Where the decoder is in the stream.
+#[derive(Debug)]
+enum State {
+    // Expecting the header of the first member.
+    Start,
+    // Expecting the header of the next block, or the trailer after the last one.
+    Block {
+        last: bool,
+    },
+    Stored {
+        remaining: usize,
+        last: bool,
+    },
+    Compressed {
+        literals: Huffman,
+        distances: Huffman,
+        last: bool,
+    },
+    // Expecting the header of another member, or the end of the input.
+    Member,
+    Done,
+}
+
+/// A decoder of gzip-compressed input, reading the decompressed bytes.
+pub(crate) struct GzDecoder<R: BufRead> {
+    input: BitReader<R>,
+    state: State,
+    // The decoded bytes, of which the ones from `pos` are not read yet, preceded by the window.
+    output: Vec<u8>,
+    pos: usize,
+    crc: u32,
+    size: u32,
+}
+
+impl<R: BufRead> GzDecoder<R> {
+    pub(crate) fn new(reader: R) -> Self {
+        Self {
+            input: BitReader {
+                reader,
+                bits: 0,
+                count: 0,
+            },
+            state: State::Start,
+            output: Vec::new(),
+            pos: 0,
+            crc: 0,
+            size: 0,
+        }
+    }
+    fn header(&mut self) -> Result<()> {
+        let input = &mut self.input;
+        if [input.byte()?, input.byte()?] != MAGIC {
+            return Err(invalid_data("not a gzip member"));
+        }
+        if input.byte()? != 8 {
+            return Err(invalid_data("unknown compression method"));
+        }
+        let flags = input.byte()?;
+        // The modification time, the extra flags, and the operating system.
+        for _ in 0..6 {
+            input.byte()?;
+        }
+        if flags & 4 != 0 {
+            let len = input.u16_le()?;
+            for _ in 0..len {
+                input.byte()?;
+            }
+        }
+        // The file name and the comment, terminated by zero.
+        for flag in [8, 16] {
+            if flags & flag != 0 {
+                while input.byte()? != 0 {}
+            }
+        }
+        if flags & 2 != 0 {
+            input.u16_le()?;
+        }
+        self.crc = 0;
+        self.size = 0;
+        Ok(())
+    }
+    fn dynamic(&mut self) -> Result<(Huffman, Huffman)> {
+        let input = &mut self.input;
+        let literals = input.bits(5)? as usize + 257;
+        let distances = input.bits(5)? as usize + 1;
+        let code_lengths = input.bits(4)? as usize + 4;
+        let mut lengths = [0u8; 19];
+        for &symbol in &CODE_LENGTH_ORDER[..code_lengths] {
+            lengths[symbol] = input.bits(3)? as u8;
+        }
+        let code = Huffman::new(&lengths)?;
+        let mut lengths = vec![0u8; literals + distances];
+        let mut i = 0;
+        while i < lengths.len() {
+            let (len, repeat) = match input.decode(&code)? {
+                symbol @ 0..=15 => (symbol as u8, 1),
+                16 if i == 0 => return Err(invalid_data("repeat with no previous length")),
+                16 => (lengths[i - 1], 3 + input.bits(2)? as usize),
+                17 => (0, 3 + input.bits(3)? as usize),
+                _ => (0, 11 + input.bits(7)? as usize),
+            };
+            if lengths.len() < i + repeat {
+                return Err(invalid_data("too many code lengths"));
+            }
+            lengths[i..i + repeat].fill(len);
+            i += repeat;
+        }
+        if lengths[256] == 0 {
+            return Err(invalid_data("no end-of-block code"));
+        }
+        let (literal_lengths, distance_lengths) = lengths.split_at(literals);
+        Ok((
+            Huffman::new(literal_lengths)?,
+            Huffman::new(distance_lengths)?,
+        ))
+    }
+    // Decode until at least `WINDOW` new bytes are available or the input ends.
+    fn decode(&mut self) -> Result<()> {
+        // Keep only the window before the unread bytes.
+        if 2 * WINDOW < self.pos {
+            self.output.drain(..self.pos - WINDOW);
+            self.pos = WINDOW;
+        }
+        let goal = self.pos + WINDOW;
+        while self.output.len() < goal {
+            let start = self.output.len();
+            let result = self.step(goal);
+            self.crc = crc32(self.crc, &self.output[start..]);
+            self.size = self.size.wrapping_add((self.output.len() - start) as u32);
+            result?;
+            if matches!(self.state, State::Done) {
+                break;
+            }
+        }
+        Ok(())
+    }
+    // Advance the state, decoding up to the `goal` length of the output.
+    fn step(&mut self, goal: usize) -> Result<()> {
+        match std::mem::replace(&mut self.state, State::Done) {
+            State::Start => {
+                self.header()?;
+                self.state = State::Block { last: false };
+            }
+            State::Member => {
+                if !self.input.is_eof()? {
+                    self.header()?;
+                    self.state = State::Block { last: false };
+                }
+            }
+            State::Done => {}
+            State::Block { last: true } => {
+                self.input.align();
+                let (crc, size) = (self.input.u32_le()?, self.input.u32_le()?);
+                if crc != self.crc || size != self.size {
+                    return Err(invalid_data("checksum mismatch"));
+                }
+                self.state = State::Member;
+            }
+            State::Block { last: false } => {
+                let last = self.input.bits(1)? == 1;
+                self.state = match self.input.bits(2)? {
+                    0 => {
+                        self.input.align();
+                        let (len, nlen) = (self.input.u16_le()?, self.input.u16_le()?);
+                        if len != !nlen {
+                            return Err(invalid_data("corrupted stored block length"));
+                        }
+                        State::Stored {
+                            remaining: len as usize,
+                            last,
+                        }
+                    }
+                    1 => {
+                        let (literals, distances) = Huffman::fixed();
+                        State::Compressed {
+                            literals,
+                            distances,
+                            last,
+                        }
+                    }
+                    2 => {
+                        let (literals, distances) = self.dynamic()?;
+                        State::Compressed {
+                            literals,
+                            distances,
+                            last,
+                        }
+                    }
+                    _ => return Err(invalid_data("invalid block type")),
+                };
+            }
+            State::Stored { remaining, last } => {
+                let len = remaining.min(goal.saturating_sub(self.output.len()).max(1));
+                for _ in 0..len {
+                    let byte = self.input.byte()?;
+                    self.output.push(byte);
+                }
+                self.state = match remaining - len {
+                    0 => State::Block { last },
+                    remaining => State::Stored { remaining, last },
+                };
+            }
+            State::Compressed {
+                literals,
+                distances,
+                last,
+            } => {
+                while self.output.len() < goal {
+                    let symbol = self.input.decode(&literals)? as usize;
+                    if symbol < 256 {
+                        self.output.push(symbol as u8);
+                        continue;
+                    } else if symbol == 256 {
+                        self.state = State::Block { last };
+                        return Ok(());
+                    }
+                    let code = symbol - 257;
+                    if LENGTH_BASE.len() <= code {
+                        return Err(invalid_data("invalid length code"));
+                    }
+                    let len = LENGTH_BASE[code] as usize
+                        + self.input.bits(LENGTH_EXTRA[code] as u32)? as usize;
+                    let code = self.input.decode(&distances)? as usize;
+                    if DIST_BASE.len() <= code {
+                        return Err(invalid_data("invalid distance code"));
+                    }
+                    let dist = DIST_BASE[code] as usize
+                        + self.input.bits(DIST_EXTRA[code] as u32)? as usize;
+                    if self.output.len() < dist {
+                        return Err(invalid_data("distance too far back"));
+                    }
+                    // The copy may overlap itself, thus byte by byte.
+                    let from = self.output.len() - dist;
+                    for i in 0..len {
+                        let byte = self.output[from + i];
+                        self.output.push(byte);
+                    }
+                }
+                self.state = State::Compressed {
+                    literals,
+                    distances,
+                    last,
+                };
+            }
+        }
+        Ok(())
+    }
+}
+
+impl<R: BufRead> Read for GzDecoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        let available = self.fill_buf()?;
+        let len = available.len().min(buf.len());
+        buf[..len].copy_from_slice(&available[..len]);
+        self.consume(len);
+        Ok(len)
+    }
+}
+
+impl<R: BufRead> BufRead for GzDecoder<R> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        if self.pos == self.output.len() {
+            self.decode()?;
+        }
+        Ok(&self.output[self.pos..])
+    }
+    fn consume(&mut self, amt: usize) {
+        self.pos = (self.pos + amt).min(self.output.len());
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    fn decode(input: &[u8]) -> Result<Vec<u8>> {
+        let mut output = Vec::new();
+        GzDecoder::new(input).read_to_end(&mut output)?;
+        Ok(output)
+    }
+    #[test]
+    fn gzip_test() {
+        // `printf 'ACGT\n' | gzip -n`, with a fixed Huffman block.
+        let fixed: &[u8] = &[
+            0x1f, 0x8b, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, 0x73, 0x74, 0x76, 0x0f,
+            0xe1, 0x02, 0x00, 0x3c, 0x9b, 0xc7, 0x61, 0x05, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(decode(fixed).unwrap(), b"ACGT\n");
+        // A stored block, in a member with a file name, concatenated to the previous member.
+        let stored: &[u8] = &[
+            0x1f, 0x8b, 0x08, 0x08, 0x00, 0x00, 0x00, 0x00, 0x00, 0x03, b'a', 0x00, 0x01, 0x03,
+            0x00, 0xfc, 0xff, b'G', b'G', b'\n', 0x8b, 0x72, 0x79, 0xd5, 0x03, 0x00, 0x00, 0x00,
+        ];
+        assert_eq!(decode(stored).unwrap(), b"GG\n");
+        assert_eq!(decode(&[fixed, stored].concat()).unwrap(), b"ACGT\nGG\n");
+        // Corrupted data and checksums, and truncated input.
+        let mut corrupted = fixed.to_vec();
+        corrupted[17] ^= 1;
+        assert_eq!(
+            decode(&corrupted).unwrap_err().kind(),
+            ErrorKind::InvalidData
+        );
+        let kind = decode(&fixed[..fixed.len() - 1]).unwrap_err().kind();
+        assert_eq!(kind, ErrorKind::UnexpectedEof);
+        assert!(decode(&[fixed, b"garbage"].concat()).is_err());
+    }
+}
@@ -0,0 +1,212 @@
+//! Substitution-only (Hamming) alignment, where insertions and deletions are not allowed.
+use crate::{
+    alphabet_len, equality_table, AlignConfig, AlignError, AlignMode, AlignTask, Alignment,
+    Reporter,
+};
+use std::convert::TryInto;
+
+// The number of bytes compared at once.
+const WORD: usize = 8;
+const LOW7: u64 = 0x7f7f_7f7f_7f7f_7f7f;
+// The number of placements of the query between checks of the cancellation.
+const CHECK_INTERVAL: usize = 1 << 10;
+
+// The number of non-zero bytes of `x`.
+fn nonzero_bytes(x: u64) -> u32 {
+    // The highest bit of a byte is set if any of its lower bits or itself is set.
+    ((((x & LOW7) + LOW7) | x) & !LOW7).count_ones()
+}
+
+// Count the mismatches between `a` and `b` of the same length, eight bytes at a time.
+// The counting stops once more than `limit` mismatches are found.
+fn count_mismatches(a: &[u8], b: &[u8], limit: u32) -> u32 {
+    let mut a_words = a.chunks_exact(WORD);
+    let mut b_words = b.chunks_exact(WORD);
+    let mut count = 0;
+    for (x, y) in a_words.by_ref().zip(b_words.by_ref()) {
+        let x = u64::from_ne_bytes(x.try_into().unwrap());
+        let y = u64::from_ne_bytes(y.try_into().unwrap());
+        count += nonzero_bytes(x ^ y);
+        if limit < count {
+            return count;
+        }
+    }
+    let rest = a_words.remainder().iter().zip(b_words.remainder());
+    count + rest.filter(|(x, y)| x != y).count() as u32
+}
+
+/// Return the Hamming distance between `a` and `b`, i.e., the number of positions with different bytes,
+/// or None if their lengths differ.
+/// # Example
+/// ```
+/// use edlib_sys::hamming_distance;
+/// assert_eq!(hamming_distance(b"ACGTACGTAC", b"ACGAACGTAG"), Some(2));
+/// assert_eq!(hamming_distance(b"ACGT", b"ACG"), None);
+/// ```
+pub fn hamming_distance(a: &[u8], b: &[u8]) -> Option<u32> {
+    (a.len() == b.len()).then(|| count_mismatches(a, b, u32::MAX))
+}
+
+/// Align the `query` to the `target` only by matches and mismatches, under the `config`.
+/// The query is placed on the target without gaps, thus the distance is the number of mismatches:
+///
+/// - Global: The sequences must have the same length.
+/// - Prefix: The query is placed at the start of the target.
+/// - Suffix: The query is placed at the end of the target.
+/// - Infix: The query is placed anywhere in the target.
+///
+/// If there is no such placement (e.g., the query is longer than the target), or the distance exceeds the `k`
+/// of the `config`, the alignment is not found. All the best placements are reported as locations,
+/// and the operations are those of the first one. The equalities of the `config` are taken into account,
+/// and without them, eight bytes are compared at once.
+/// The [`AlignMode::Overlap`] and [`AlignMode::Local`] modes are not supported, and return
+/// [`AlignError::UnsupportedMode`].
+/// # Example
+/// ```
+/// use edlib_sys::{align_hamming, AlignConfig, AlignMode, AlignTask};
+/// let config = AlignConfig::new(AlignMode::Infix, AlignTask::Alignment);
+/// // The edit distance is 1 by deleting `T`.
+/// let aln = align_hamming(b"ACGTAC", b"GGACGACGG", &config).unwrap();
+/// assert_eq!(aln.dist(), 3);
+/// assert_eq!(aln.location(), Some((2, 7)));
+/// assert_eq!(aln.operations(), Some([0, 0, 0, 3, 3, 3].as_slice()));
+/// let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance);
+/// assert!(!align_hamming(b"ACGT", b"ACGTT", &config).unwrap().is_found());
+/// ```
+pub fn align_hamming(
+    query: &[u8],
+    target: &[u8],
+    config: &AlignConfig,
+) -> Result<Alignment, AlignError> {
+    let (n, m) = (query.len(), target.len());
+    // The candidate start positions of the query on the target.
+    let starts = match config.mode {
+        AlignMode::Overlap | AlignMode::Local => {
+            return Err(AlignError::UnsupportedMode(config.mode))
+        }
+        AlignMode::Global if n == m => 0..1,
+        AlignMode::Prefix if n <= m => 0..1,
+        AlignMode::Suffix if n <= m => m - n..m - n + 1,
+        AlignMode::Infix if n <= m => 0..m - n + 1,
+        _ => 0..0,
+    };
+    let table = (!config.equalities.is_empty()).then(|| equality_table(&config.equalities));
+    let is_equal = |a: u8, b: u8| table.as_ref().map_or(a == b, |is_equal| is_equal(a, b));
+    let reporter = Reporter::new(config);
+    let placements = starts.len();
+    let mut best: Option<u32> = None;
+    let mut best_starts = Vec::new();
+    for (i, start) in starts.enumerate() {
+        if i % CHECK_INTERVAL == 0 {
+            if config.is_cancelled() {
+                return Err(AlignError::Cancelled);
+            }
+            reporter.report(i as f64 / placements as f64, None);
+        }
+        // Placements worse than the best one so far are abandoned.
+        let limit = best.or(config.k).unwrap_or(u32::MAX);
+        let placed = &target[start..start + n];
+        let dist = match table {
+            Some(_) => {
+                let pairs = query.iter().zip(placed);
+                pairs.filter(|&(&q, &t)| !is_equal(q, t)).count() as u32
+            }
+            None => count_mismatches(query, placed, limit),
+        };
+        if limit < dist {
+            continue;
+        }
+        if best != Some(dist) {
+            best = Some(dist);
+            best_starts.clear();
+        }
+        best_starts.push(start);
+    }
+    reporter.report(1.0, None);
+    let operations = match (config.task, best_starts.first()) {
+        (AlignTask::Alignment, Some(&start)) => query
+            .iter()
+            .zip(&target[start..])
+            .map(|(&q, &t)| if is_equal(q, t) { 0 } else { 3 })
+            .collect(),
+        _ => Vec::new(),
+    };
+    Ok(Alignment {
+        dist: best,
+        starts: best_starts.iter().map(|&start| start as i32).collect(),
+        // The end is `start - 1` for an empty query, as in edlib.
+        ends: best_starts
+            .iter()
+            .map(|&start| (start + n) as i32 - 1)
+            .collect(),
+        operations,
+        query_location: Some((0, n as i32 - 1)),
+        strategy: None,
+        k: None,
+        rounds: 0,
+        alphabet_len: alphabet_len(query, target),
+        task: config.task,
+        mode: config.mode,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn count_test() {
+        let a = b"ACGTACGTACGTACGTACGTA";
+        for i in 0..a.len() {
+            let mut b = a.to_vec();
+            b[i] = b'N';
+            b[a.len() - 1 - i] = 0x80;
+            let expected = a.iter().zip(&b).filter(|(x, y)| x != y).count() as u32;
+            assert_eq!(hamming_distance(a, &b), Some(expected));
+        }
+        // The counting stops at the first word exceeding the limit.
+        assert_eq!(count_mismatches(&[0; 24], &[1; 24], 3), 8);
+        assert_eq!(hamming_distance(b"", b""), Some(0));
+    }
+    #[test]
+    fn hamming_test() {
+        let target = b"TTACGTTTACGATTACCTTT";
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location);
+        let aln = align_hamming(b"ACGT", target, &config).unwrap();
+        assert_eq!(aln.dist(), 0);
+        assert_eq!(aln.locations(), Some(([2].as_slice(), [5].as_slice())));
+        let aln = align_hamming(b"ACGG", target, &config).unwrap();
+        assert_eq!(aln.dist(), 1);
+        let expected: (&[i32], &[i32]) = (&[2, 8], &[5, 11]);
+        assert_eq!(aln.locations(), Some(expected));
+        assert!(!align_hamming(b"GGGG", target, &config.clone().with_k(1))
+            .unwrap()
+            .is_found());
+        let config = AlignConfig::new(AlignMode::Prefix, AlignTask::Alignment);
+        let aln = align_hamming(b"TTAGG", target, &config).unwrap();
+        assert_eq!((aln.dist(), aln.location()), (1, Some((0, 4))));
+        assert_eq!(aln.operations(), Some([0, 0, 0, 3, 0].as_slice()));
+        let config = AlignConfig::new(AlignMode::Suffix, AlignTask::Location);
+        let aln = align_hamming(b"CCTTA", target, &config).unwrap();
+        assert_eq!((aln.dist(), aln.location()), (1, Some((15, 19))));
+        // `N` matches any base.
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment).with_equalities(&[
+            (b'N', b'A'),
+            (b'N', b'C'),
+            (b'N', b'G'),
+            (b'N', b'T'),
+        ]);
+        let aln = align_hamming(b"ACNNAC", b"ACGTAA", &config).unwrap();
+        assert_eq!(aln.dist(), 1);
+        assert_eq!(aln.operations(), Some([0, 0, 0, 0, 0, 3].as_slice()));
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Distance);
+        assert!(!align_hamming(target, b"ACGT", &config).unwrap().is_found());
+    }
+    #[test]
+    fn overlap_test() {
+        for mode in [AlignMode::Overlap, AlignMode::Local] {
+            let config = AlignConfig::new(mode, AlignTask::Distance);
+            let result = align_hamming(b"ACGT", b"ACGT", &config);
+            assert_eq!(result, Err(AlignError::UnsupportedMode(mode)));
+        }
+    }
+}
@@ -0,0 +1,524 @@
+//! # FASTA/FASTQ input
+//!
+//! A small reader for FASTA and FASTQ files, without any dependency, together with a driver
+//! that aligns every record of a query file against the records of a reference file.
+//!
+//! The format is detected from the first byte of each record: `>` for FASTA and `@` for FASTQ.
+//! Gzip-compressed input is detected by its magic bytes and decompressed on the fly, without any dependency either;
+//! concatenated gzip members (e.g., from `cat a.fa.gz b.fa.gz`) are read one after another.
+//! Multi-line FASTA records are supported, and so are FASTQ records whose sequence and quality span several lines.
+//!
+//! This module is available only when the `io` feature is enabled.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::io::Reader;
+//! let input: &[u8] = b">read1 first read\nACGT\nACGT\n>read2\nCCC\n";
+//! let records: Vec<_> = Reader::new(input).collect::<Result<_, _>>().unwrap();
+//! assert_eq!(records.len(), 2);
+//! assert_eq!(records[0].id(), "read1");
+//! assert_eq!(records[0].desc(), Some("first read"));
+//! assert_eq!(records[0].seq(), b"ACGTACGT");
+//! assert_eq!(records[1].seq(), b"CCC");
+//! ```
+use crate::gzip::{GzDecoder, MAGIC};
+use crate::{try_align_with, AlignConfig, AlignError, AlignMode, AlignTask, Alignment};
+use std::fs::File;
+use std::io::{BufRead, BufReader, Error, ErrorKind, Read, Result};
+use std::path::Path;
+
+/// A FASTA or FASTQ record.
+/// The quality string is `None` for FASTA records.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    id: String,
+    desc: Option<String>,
+    seq: Vec<u8>,
+    qual: Option<Vec<u8>>,
+}
+
+impl Record {
+    /// Create a FASTA record.
+    pub fn new(id: &str, desc: Option<&str>, seq: &[u8]) -> Self {
+        Self {
+            id: id.to_string(),
+            desc: desc.map(|d| d.to_string()),
+            seq: seq.to_vec(),
+            qual: None,
+        }
+    }
+    /// Create a FASTQ record. `qual` should have the same length as `seq`.
+    pub fn with_qual(id: &str, desc: Option<&str>, seq: &[u8], qual: &[u8]) -> Self {
+        Self {
+            qual: Some(qual.to_vec()),
+            ..Self::new(id, desc, seq)
+        }
+    }
+    /// Return the identifier, i.e., the header up to the first whitespace.
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+    /// Return the rest of the header line, if any.
+    pub fn desc(&self) -> Option<&str> {
+        self.desc.as_deref()
+    }
+    /// Return the sequence.
+    pub fn seq(&self) -> &[u8] {
+        &self.seq
+    }
+    /// Return the quality string. Return None for FASTA records.
+    pub fn qual(&self) -> Option<&[u8]> {
+        self.qual.as_deref()
+    }
+    /// Return true if the record came from (or is written as) FASTQ.
+    pub fn is_fastq(&self) -> bool {
+        self.qual.is_some()
+    }
+}
+
+impl std::fmt::Display for Record {
+    /// Write the record in FASTA or FASTQ format, with the sequence on a single line.
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let header = match self.desc.as_deref() {
+            Some(desc) => format!("{} {}", self.id, desc),
+            None => self.id.clone(),
+        };
+        let seq = String::from_utf8_lossy(&self.seq);
+        match self.qual.as_deref() {
+            Some(qual) => {
+                let qual = String::from_utf8_lossy(qual);
+                write!(f, "@{}\n{}\n+\n{}", header, seq, qual)
+            }
+            None => write!(f, ">{}\n{}", header, seq),
+        }
+    }
+}
+
+/// A streaming reader of FASTA/FASTQ records.
+/// It implements `Iterator<Item = std::io::Result<Record>>`.
+#[derive(Debug)]
+pub struct Reader<R: BufRead> {
+    reader: R,
+    line: Vec<u8>,
+    // The header line of the next record, if it has already been read.
+    header: Option<Vec<u8>>,
+    done: bool,
+}
+
+impl<R: BufRead> Reader<R> {
+    /// Create a reader from a buffered reader of plain (not compressed) text.
+    pub fn new(reader: R) -> Self {
+        Self {
+            reader,
+            line: Vec::new(),
+            header: None,
+            done: false,
+        }
+    }
+    // Read the next line into `self.line` without the trailing newline.
+    // Return false at the end of the stream.
+    fn next_line(&mut self) -> Result<bool> {
+        self.line.clear();
+        if self.reader.read_until(b'\n', &mut self.line)? == 0 {
+            return Ok(false);
+        }
+        while matches!(self.line.last(), Some(b'\n') | Some(b'\r')) {
+            self.line.pop();
+        }
+        Ok(true)
+    }
+    fn next_record(&mut self) -> Result<Option<Record>> {
+        let header = match self.header.take() {
+            Some(header) => header,
+            None => loop {
+                if !self.next_line()? {
+                    return Ok(None);
+                } else if !self.line.is_empty() {
+                    break std::mem::take(&mut self.line);
+                }
+            },
+        };
+        let (id, desc) = parse_header(&header[1..]);
+        match header[0] {
+            b'>' => self.read_fasta(id, desc).map(Some),
+            b'@' => self.read_fastq(id, desc).map(Some),
+            c => Err(invalid_data(format!(
+                "expected '>' or '@' at the start of a record, found '{}'",
+                c as char
+            ))),
+        }
+    }
+    fn read_fasta(&mut self, id: String, desc: Option<String>) -> Result<Record> {
+        let mut seq = Vec::new();
+        while self.next_line()? {
+            if self.line.first() == Some(&b'>') {
+                self.header = Some(std::mem::take(&mut self.line));
+                break;
+            }
+            seq.extend(self.line.iter().filter(|c| !c.is_ascii_whitespace()));
+        }
+        Ok(Record {
+            id,
+            desc,
+            seq,
+            qual: None,
+        })
+    }
+    fn read_fastq(&mut self, id: String, desc: Option<String>) -> Result<Record> {
+        let mut seq = Vec::new();
+        loop {
+            if !self.next_line()? {
+                return Err(invalid_data(format!("record {} has no '+' line", id)));
+            } else if self.line.first() == Some(&b'+') {
+                break;
+            }
+            seq.extend(self.line.iter().filter(|c| !c.is_ascii_whitespace()));
+        }
+        let mut qual = Vec::with_capacity(seq.len());
+        while qual.len() < seq.len() {
+            if !self.next_line()? {
+                return Err(invalid_data(format!("record {} is truncated", id)));
+            }
+            qual.extend(self.line.iter().filter(|c| !c.is_ascii_whitespace()));
+        }
+        if qual.len() != seq.len() {
+            return Err(invalid_data(format!(
+                "record {} has {} bases but {} qualities",
+                id,
+                seq.len(),
+                qual.len()
+            )));
+        }
+        Ok(Record {
+            id,
+            desc,
+            seq,
+            qual: Some(qual),
+        })
+    }
+}
+
+impl<R: BufRead> Iterator for Reader<R> {
+    type Item = Result<Record>;
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.done {
+            return None;
+        }
+        let record = self.next_record();
+        if !matches!(record, Ok(Some(_))) {
+            self.done = true;
+        }
+        record.transpose()
+    }
+}
+
+fn parse_header(header: &[u8]) -> (String, Option<String>) {
+    let header = String::from_utf8_lossy(header);
+    let mut fields = header.trim_end().splitn(2, char::is_whitespace);
+    let id = fields.next().unwrap_or("").to_string();
+    let desc = fields
+        .next()
+        .map(|desc| desc.trim_start().to_string())
+        .filter(|desc| !desc.is_empty());
+    (id, desc)
+}
+
+fn invalid_data(msg: String) -> Error {
+    Error::new(ErrorKind::InvalidData, msg)
+}
+
+/// The input of a [`Reader`] created by [`from_reader`] or [`open`]:
+/// the text itself, or the text decompressed from gzip.
+pub struct Decoder<R: Read>(Input<R>);
+
+enum Input<R: Read> {
+    Plain(BufReader<R>),
+    Gzip(GzDecoder<BufReader<R>>),
+}
+
+impl<R: Read> Decoder<R> {
+    /// Return true if the input is gzip-compressed.
+    pub fn is_gzip(&self) -> bool {
+        matches!(self.0, Input::Gzip(_))
+    }
+}
+
+impl<R: Read> std::fmt::Debug for Decoder<R> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Decoder")
+            .field("gzip", &self.is_gzip())
+            .finish()
+    }
+}
+
+impl<R: Read> Read for Decoder<R> {
+    fn read(&mut self, buf: &mut [u8]) -> Result<usize> {
+        match &mut self.0 {
+            Input::Plain(reader) => reader.read(buf),
+            Input::Gzip(reader) => reader.read(buf),
+        }
+    }
+}
+
+impl<R: Read> BufRead for Decoder<R> {
+    fn fill_buf(&mut self) -> Result<&[u8]> {
+        match &mut self.0 {
+            Input::Plain(reader) => reader.fill_buf(),
+            Input::Gzip(reader) => reader.fill_buf(),
+        }
+    }
+    fn consume(&mut self, amt: usize) {
+        match &mut self.0 {
+            Input::Plain(reader) => reader.consume(amt),
+            Input::Gzip(reader) => reader.consume(amt),
+        }
+    }
+}
+
+/// Wrap a reader of FASTA/FASTQ text into a [`Reader`].
+/// Gzip-compressed input is detected by its magic bytes and decompressed;
+/// corrupted or truncated gzip data is reported as an error by the [`Reader`].
+pub fn from_reader<R: Read>(reader: R) -> Result<Reader<Decoder<R>>> {
+    let mut reader = BufReader::new(reader);
+    let input = match reader.fill_buf()?.starts_with(&MAGIC) {
+        true => Input::Gzip(GzDecoder::new(reader)),
+        false => Input::Plain(reader),
+    };
+    Ok(Reader::new(Decoder(input)))
+}
+
+/// Open a FASTA/FASTQ file, plain or gzip-compressed. See [`from_reader`].
+pub fn open<P: AsRef<Path>>(path: P) -> Result<Reader<Decoder<File>>> {
+    from_reader(File::open(path)?)
+}
+
+/// Read all the records of a FASTA/FASTQ file into memory.
+pub fn read_all<P: AsRef<Path>>(path: P) -> Result<Vec<Record>> {
+    open(path)?.collect()
+}
+
+/// A query record aligned to the reference records.
+/// The `alignments` are in the order of the references given to the [`Driver`].
+#[derive(Debug)]
+pub struct AlignedRecord<'a> {
+    pub query: Record,
+    pub alignments: Vec<(&'a Record, Alignment)>,
+}
+
+/// A driver aligning query records to a set of reference records held in memory.
+/// # Example
+/// ```
+/// use edlib_sys::io::{Driver, Reader, Record};
+/// use edlib_sys::{AlignMode, AlignTask};
+/// let references = vec![Record::new("chr1", None, b"AAACCCTTT"), Record::new("chr2", None, b"GGGGG")];
+/// let queries: &[u8] = b">q1\nCCC\n";
+/// let driver = Driver::new(&references, AlignMode::Infix, AlignTask::Location);
+/// let results: Vec<_> = driver.run(Reader::new(queries)).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(results.len(), 1);
+/// let (reference, aln) = &results[0].alignments[0];
+/// assert_eq!(reference.id(), "chr1");
+/// assert_eq!(aln.dist(), 0);
+/// assert_eq!(aln.location(), Some((3, 5)));
+/// // Restrict the references to the named one.
+/// let driver = driver.only("chr2").unwrap();
+/// let results: Vec<_> = driver.run(Reader::new(queries)).collect::<Result<_, _>>().unwrap();
+/// assert_eq!(results[0].alignments.len(), 1);
+/// assert_eq!(results[0].alignments[0].0.id(), "chr2");
+/// ```
+#[derive(Debug, Clone)]
+pub struct Driver<'a> {
+    references: Vec<&'a Record>,
+    config: AlignConfig,
+}
+
+impl<'a> Driver<'a> {
+    /// Create a driver aligning queries to all the `references`.
+    pub fn new(references: &'a [Record], mode: AlignMode, task: AlignTask) -> Self {
+        Self::with_config(references, AlignConfig::new(mode, task))
+    }
+    /// Create a driver aligning queries to all the `references` under the `config`.
+    pub fn with_config(references: &'a [Record], config: AlignConfig) -> Self {
+        Self {
+            references: references.iter().collect(),
+            config,
+        }
+    }
+    /// Restrict the references to the record named `id`.
+    /// Return None if there is no such reference.
+    pub fn only(mut self, id: &str) -> Option<Self> {
+        self.references.retain(|r| r.id() == id);
+        (!self.references.is_empty()).then_some(self)
+    }
+    /// Return the configuration of the alignments.
+    pub fn config(&self) -> &AlignConfig {
+        &self.config
+    }
+    /// Return the references the queries will be aligned to.
+    pub fn references(&self) -> &[&'a Record] {
+        &self.references
+    }
+    /// Align a single query to the references.
+    /// Return [`AlignError::Cancelled`] if the cancel token or the deadline of the configuration stops an alignment.
+    pub fn align(&self, query: Record) -> std::result::Result<AlignedRecord<'a>, AlignError> {
+        let alignments = self
+            .references
+            .iter()
+            .map(|&r| Ok((r, try_align_with(query.seq(), r.seq(), &self.config)?)))
+            .collect::<std::result::Result<_, AlignError>>()?;
+        Ok(AlignedRecord { query, alignments })
+    }
+    /// Align every record of `queries` to the references, lazily.
+    /// Reading errors are passed through, and so are cancelled alignments, as errors of [`ErrorKind::Other`]
+    /// wrapping the [`AlignError`]. The iteration stops after the first error.
+    pub fn run<'b, I>(&'b self, queries: I) -> impl Iterator<Item = Result<AlignedRecord<'a>>> + 'b
+    where
+        I: IntoIterator<Item = Result<Record>>,
+        I::IntoIter: 'b,
+    {
+        queries.into_iter().scan(false, move |failed, q| {
+            if *failed {
+                return None;
+            }
+            let aligned = q.and_then(|q| self.align(q).map_err(Error::other));
+            *failed = aligned.is_err();
+            Some(aligned)
+        })
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn fastq_test() {
+        let input: &[u8] = b"@r1 desc\nACGT\nAC\n+\n!!!!\n!!\n@r2\nGG\n+r2\n@@\n";
+        let records: Vec<_> = Reader::new(input).collect::<Result<_>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].seq(), b"ACGTAC");
+        assert_eq!(records[0].qual(), Some(b"!!!!!!".as_slice()));
+        assert_eq!(records[1].id(), "r2");
+        assert_eq!(records[1].qual(), Some(b"@@".as_slice()));
+        // Whitespace in the sequence and quality lines is ignored.
+        let input: &[u8] = b"@r1\r\nAC GT\r\n+\r\n!! !!\r\n";
+        let record = Reader::new(input).next().unwrap().unwrap();
+        assert_eq!(record, Record::with_qual("r1", None, b"ACGT", b"!!!!"));
+        let truncated: &[u8] = b"@r1\nACGT\n+\n!!\n";
+        assert!(Reader::new(truncated).next().unwrap().is_err());
+    }
+    #[test]
+    fn fasta_test() {
+        let input: &[u8] = b"\n>r1 first  read \r\nAC GT\r\n\nTT\n>r2\n>r3\tx\nGG";
+        let records: Vec<_> = Reader::new(input).collect::<Result<_>>().unwrap();
+        assert_eq!(
+            records,
+            vec![
+                Record::new("r1", Some("first  read"), b"ACGTTT"),
+                Record::new("r2", None, b""),
+                Record::new("r3", Some("x"), b"GG"),
+            ]
+        );
+        assert_eq!(records[0].to_string(), ">r1 first  read\nACGTTT");
+        let fastq = Record::with_qual("q", None, b"AC", b"!!");
+        assert_eq!(fastq.to_string(), "@q\nAC\n+\n!!");
+        assert!(Reader::new(b"".as_slice()).next().is_none());
+    }
+    #[test]
+    fn malformed_test() {
+        let is_invalid = |input: &[u8]| {
+            let mut reader = Reader::new(input);
+            let kind = reader.next().unwrap().unwrap_err().kind();
+            // The iteration stops after an error.
+            kind == ErrorKind::InvalidData && reader.next().is_none()
+        };
+        assert!(is_invalid(b"ACGT\n>r1\nACGT\n"));
+        assert!(is_invalid(b"@r1\nACGT\n"));
+        assert!(is_invalid(b"@r1\nACGT\n+\n!!!!!\n"));
+        // An error after valid records.
+        let input: &[u8] = b"@r1\nAC\n+\n!!\nXX\n";
+        let records: Vec<_> = Reader::new(input).collect();
+        assert_eq!(records.len(), 2);
+        assert!(records[0].is_ok() && records[1].is_err());
+        let gzip: &[u8] = &[0x1f, 0x8b, 0x08, 0x00];
+        let mut reader = from_reader(gzip).unwrap();
+        assert!(reader.next().unwrap().is_err() && reader.next().is_none());
+        assert!(!from_reader(b">r1\nAC\n".as_slice())
+            .unwrap()
+            .reader
+            .is_gzip());
+    }
+    #[test]
+    fn driver_test() {
+        let references = vec![
+            Record::new("t1", None, b"AAACCCGGG"),
+            Record::new("t2", None, b"TTTTACGTTTT"),
+        ];
+        let driver = Driver::new(&references, AlignMode::Infix, AlignTask::Location);
+        let queries: &[u8] = b"@q1\nACGT\n+\nIIII\n@q2\nCCCG\n+\nIIII\n";
+        let results: Vec<_> = driver
+            .run(Reader::new(queries))
+            .collect::<Result<_>>()
+            .unwrap();
+        assert_eq!(results.len(), 2);
+        assert_eq!(results[0].query.id(), "q1");
+        assert!(results[0].query.is_fastq());
+        let dists: Vec<Vec<_>> = results
+            .iter()
+            .map(|r| r.alignments.iter().map(|(_, aln)| aln.dist()).collect())
+            .collect();
+        assert_eq!(dists, vec![vec![2, 0], vec![0, 2]]);
+        assert_eq!(results[0].alignments[1].1.location(), Some((4, 7)));
+        assert_eq!(results[1].alignments[0].0.id(), "t1");
+        // Reading errors are passed through, and the iteration stops there.
+        let queries = Reader::new(b">q1\nACGT\nACGT\n".as_slice());
+        let mut results = driver.run(queries.chain(Reader::new(b"ACGT".as_slice())));
+        assert!(results.next().unwrap().is_ok());
+        assert!(results.next().unwrap().is_err());
+        assert!(results.next().is_none());
+        let error = || Err(invalid_data("read error".to_string()));
+        let queries = vec![error(), Ok(Record::new("q", None, b"ACGT"))];
+        let mut results = driver.run(queries);
+        assert!(results.next().unwrap().is_err());
+        assert!(results.next().is_none());
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance).with_k(1);
+        let driver = Driver::with_config(&references, config).only("t2").unwrap();
+        assert_eq!(driver.references().len(), 1);
+        let aligned = driver
+            .align(Record::new("q", None, b"TTTTACGTTTA"))
+            .unwrap();
+        assert_eq!(aligned.alignments[0].1.dist(), 1);
+        assert!(driver.only("t1").is_none());
+        // A cancelled alignment is an error, which stops the iteration.
+        let token = crate::CancelToken::new();
+        token.cancel();
+        let config =
+            AlignConfig::new(AlignMode::Global, AlignTask::Distance).with_cancel_token(token);
+        let driver = Driver::with_config(&references, config);
+        let query = Record::new("q", None, b"ACGT");
+        assert_eq!(
+            driver.align(query.clone()).unwrap_err(),
+            AlignError::Cancelled
+        );
+        let mut results = driver.run(vec![Ok(query.clone()), Ok(query)]);
+        assert_eq!(
+            results.next().unwrap().unwrap_err().kind(),
+            ErrorKind::Other
+        );
+        assert!(results.next().is_none());
+    }
+    #[test]
+    fn gzip_test() {
+        let data = concat!(env!("CARGO_MANIFEST_DIR"), "/tests/data/");
+        // The members are a stored block, a fixed Huffman block, and a dynamic Huffman block.
+        let gzip = open(format!("{}reads.fa.gz", data)).unwrap();
+        assert!(gzip.reader.is_gzip());
+        let records = gzip.collect::<Result<Vec<_>>>().unwrap();
+        assert_eq!(records, read_all(format!("{}reads.fa", data)).unwrap());
+        assert_eq!(records.len(), 3);
+        assert_eq!(records[1], Record::new("read2", None, b"ACGTACGT"));
+        // Back-references beyond the window of the first decoded bytes.
+        let records = read_all(format!("{}long.fa.gz", data)).unwrap();
+        let seq = records[0].seq();
+        assert_eq!(seq.len(), 120_000);
+        assert!((30_000..seq.len()).all(|i| seq[i] == seq[i - 30_000]));
+    }
+}
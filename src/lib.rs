@@ -43,6 +43,24 @@
 //! ## What does this library provide
 //!
 //! This crate provides a single API that call edlib's edit distance computation.
+//! On top of it, the [`extend`] module extends seed hits to both sides, and [`align_banded`]
+//! aligns nearly collinear sequences in a diagonal band.
+//! [`align_damerau`] counts the transposition of two adjacent characters as a single edit.
+//! [`align_hamming`] aligns sequences only by substitutions, such as fixed-length barcodes.
+//! [`align_symbols`] aligns sequences of any hashable symbols, such as tokens or k-mer IDs.
+//! The [`text`] module aligns strings by characters or grapheme clusters.
+//! The [`matrix`] module computes all-vs-all distance matrices, and the [`cluster`] module
+//! collapses UMIs and barcodes within a small edit distance.
+//! The [`demux`] module assigns reads to barcodes in a whitelist, and the [`trim`] module trims adapters.
+//! The [`fuzzy`] module provides the Levenshtein distance, the normalized similarity, and ranked fuzzy matches
+//! of short strings such as names.
+//! The [`edit`] module applies alignment operations as edit scripts and validates alignments.
+//! The [`alphabet`] module validates and normalizes DNA, RNA, protein, and custom sequences before the alignment.
+//!
+//! With the `io` feature, the [`io`] module provides a FASTA/FASTQ reader and a driver to align
+//! the records of a query file against those of a reference file.
+//! With the `graphemes` feature, the [`text`] module can align strings by extended grapheme clusters.
+//! With the `cli` feature, an `edlib` binary similar to edlib's `edlib-aligner` is built as well.
 //!
 //! ## Example
 //! ```rust
@@ -59,7 +77,31 @@
 //!
 
 // include!(concat!(env!("OUT_DIR"), "/bindings.rs"));
+pub mod alphabet;
+mod banded;
 mod bindings;
+pub mod cluster;
+mod damerau;
+pub mod demux;
+pub mod edit;
+pub mod extend;
+pub mod fuzzy;
+#[cfg(feature = "io")]
+mod gzip;
+mod hamming;
+#[cfg(feature = "io")]
+pub mod io;
+mod local;
+pub mod matrix;
+mod myers;
+mod overlap;
+mod symbols;
+pub mod text;
+pub mod trim;
+pub use banded::align_banded;
+pub use damerau::align_damerau;
+pub use hamming::{align_hamming, hamming_distance};
+pub use symbols::align_symbols;
 
 /// A struct for the result of an alignment computed by `edlib`.
 /// All the members are not accessible from the callers, so use methods such as [`Alignment::location()`] or [`Alignment::operations()`] to
@@ -75,17 +117,42 @@ mod bindings;
 /// assert_eq!(aln.dist(), 0);
 /// assert_eq!(aln.operations(), Some([0, 0, 0].as_slice()));
 /// ```
-#[derive(Debug, Clone)]
+///
+/// The result owns its memory -- the arrays allocated by edlib are copied and released
+/// as soon as the alignment finishes.
+/// With the `serde` feature, it can be serialized as
+/// `{"mode": .., "task": .., "dist": .., "locations": [[start, end], ..], "query_location": [start, end], "operations": ..}`,
+/// where `dist` is null if the alignment is not found and `operations` is an extended CIGAR string (or null).
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(
+    feature = "serde",
+    derive(serde::Serialize, serde::Deserialize),
+    serde(into = "SerializedAlignment", try_from = "SerializedAlignment")
+)]
 pub struct Alignment {
-    align: bindings::EdlibAlignResult,
+    dist: Option<u32>,
+    starts: Vec<i32>,
+    ends: Vec<i32>,
+    operations: Vec<u8>,
+    query_location: Option<(i32, i32)>,
+    strategy: Option<TracebackStrategy>,
+    k: Option<u32>,
+    rounds: u32,
+    alphabet_len: usize,
     task: AlignTask,
     mode: AlignMode,
 }
 
 impl Alignment {
     /// Return the edit distance.
+    /// If `k` was given by [`AlignConfig::with_k`] and the distance exceeds it, return `u32::MAX`.
     pub fn dist(&self) -> u32 {
-        self.align.editDistance as u32
+        self.dist.unwrap_or(u32::MAX)
+    }
+    /// Return false if `k` was given by [`AlignConfig::with_k`] and the distance exceeds it.
+    /// In that case, neither locations nor operations are available.
+    pub fn is_found(&self) -> bool {
+        self.dist.is_some()
     }
     /// Return the range of the `target` sequence that aligns to the `query`.
     /// Note that the end coordinate is *1-index*!
@@ -93,27 +160,40 @@ impl Alignment {
     /// one needs to slice the original (reference) string `target` as `target[start..end+1]` to
     /// obtain the sequence that matches to the query string.
     /// Use `self.locations()` to obtain all the locations.
-    /// Return None if the alignment task is [`AlignTask::Distance`].
-    /// # Example
+    /// If the query is aligned to an empty segment, `end` is `start - 1`, wrapping to `usize::MAX` at the start
+    /// of the target; slice it as `target[start..end.wrapping_add(1)]`.
+    /// Return None if the alignment task is [`AlignTask::Distance`] or the alignment is not found.
     pub fn location(&self) -> Option<(usize, usize)> {
-        (self.task != AlignTask::Distance).then(|| unsafe {
-            let length = self.align.numLocations as usize;
-            let starts = std::slice::from_raw_parts(self.align.startLocations, length);
-            let ends = std::slice::from_raw_parts(self.align.endLocations, length);
-            (starts[0] as usize, ends[0] as usize)
-        })
+        let (starts, ends) = self.locations()?;
+        Some((*starts.first()? as usize, *ends.first()? as usize))
     }
     /// Return all the alignment location of the `target` that aligns to the `query`.
     /// Return None if the alignment task is `[AlignTask::Distance]`.
     /// To supress the memory allocation, this function returns the slice of `i32` -- it
     /// is the responsibility of the callers to convert the `i32` into `usize`.
     pub fn locations(&self) -> Option<(&[i32], &[i32])> {
-        (self.task != AlignTask::Distance).then(|| unsafe {
-            let length = self.align.numLocations as usize;
-            let starts = std::slice::from_raw_parts(self.align.startLocations, length);
-            let ends = std::slice::from_raw_parts(self.align.endLocations, length);
-            (starts, ends)
-        })
+        (self.task != AlignTask::Distance && self.is_found())
+            .then_some((self.starts.as_slice(), self.ends.as_slice()))
+    }
+    /// Return the range of the `query` sequence that aligns to the `target`, in the same
+    /// convention as [`Alignment::location()`] (the end coordinate is inclusive).
+    /// It is the entire query except in the [`AlignMode::Overlap`] and [`AlignMode::Local`] modes.
+    /// Return None if the alignment task is [`AlignTask::Distance`] or the alignment is not found.
+    /// # Example
+    /// ```
+    /// use edlib_sys::{align, AlignTask, AlignMode};
+    /// // The suffix of the query overlaps with the prefix of the target.
+    /// let query =  b"TTTTTTACGTACGT";
+    /// let target = b"ACGTACGTGGGGGG";
+    /// let aln = align(query, target, AlignMode::Overlap, AlignTask::Location);
+    /// assert_eq!(aln.dist(), 0);
+    /// assert_eq!(aln.query_location(), Some((6, 13)));
+    /// assert_eq!(aln.location(), Some((0, 7)));
+    /// ```
+    pub fn query_location(&self) -> Option<(usize, usize)> {
+        let (start, end) = self.query_location?;
+        (self.task != AlignTask::Distance && self.is_found())
+            .then_some((start as usize, end as usize))
     }
     /// Return the alignment operation of the alignment between
     /// `target` sequence and `query` sequence.
@@ -121,7 +201,8 @@ impl Alignment {
     /// target sequence and the entire query sequence.
     /// In other words, if one use [`AlignMode`] other than [`AlignMode::Global`],
     /// one needs to call [`Alignment::location()`] to get the aligned region of the
-    /// `target` sequence.
+    /// `target` sequence. In [`AlignMode::Overlap`] and [`AlignMode::Local`], only a part of the query is aligned as well,
+    /// which is given by [`Alignment::query_location()`].
     ///
     /// The alignment operations are defined as follows:
     ///
@@ -162,18 +243,236 @@ impl Alignment {
     /// assert_eq!(aln.operations(), Some([2, 0, 0, 0, 0, 1, 0].as_slice()));
     /// ```
     pub fn operations(&self) -> Option<&[u8]> {
-        (self.task == AlignTask::Alignment).then(|| unsafe {
-            let length = self.align.alignmentLength as usize;
-            std::slice::from_raw_parts(self.align.alignment, length)
-        })
+        (self.task == AlignTask::Alignment && self.is_found()).then_some(self.operations.as_slice())
+    }
+    /// Return the CIGAR string of the alignment operations.
+    /// Return None if the operations are not available (see [`Alignment::operations()`]).
+    /// # Example
+    /// ```
+    /// use edlib_sys::{align, AlignTask, AlignMode, CigarFormat};
+    /// let aln = align(b"CGTCCG", b"ACGTCGT", AlignMode::Prefix, AlignTask::Alignment);
+    /// assert_eq!(aln.cigar(CigarFormat::Standard).unwrap(), "1D4M1I1M");
+    /// assert_eq!(aln.cigar(CigarFormat::Extended).unwrap(), "1D4=1I1=");
+    /// ```
+    pub fn cigar(&self, format: CigarFormat) -> Option<String> {
+        Some(cigar(self.operations()?, format))
+    }
+    /// Return how the alignment operations were computed, which depends on
+    /// the memory budget given by [`AlignConfig::with_memory_budget`].
+    /// Return None if the operations are not available (see [`Alignment::operations()`]).
+    /// # Example
+    /// ```
+    /// use edlib_sys::{align_with, AlignConfig, AlignTask, AlignMode, TracebackStrategy};
+    /// let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment);
+    /// let aln = align_with(b"ACGTACGT", b"ACGTTCGT", &config);
+    /// assert_eq!(aln.strategy(), Some(TracebackStrategy::Traceback));
+    /// let aln = align_with(b"ACGTACGT", b"ACGTTCGT", &config.with_memory_budget(0));
+    /// assert_eq!(aln.strategy(), Some(TracebackStrategy::Hirschberg));
+    /// assert_eq!(aln.operations(), Some([0, 0, 0, 0, 3, 0, 0, 0].as_slice()));
+    /// ```
+    pub fn strategy(&self) -> Option<TracebackStrategy> {
+        self.operations().and(self.strategy)
+    }
+    /// Return the upper bound of the distance in the last pass of edlib over the target: the `k` given by
+    /// [`AlignConfig::with_k`], or the one reached by doubling `k` from 64 until the alignment is found.
+    /// Return None if edlib did not run any pass (e.g., either sequence is empty).
+    /// In the overlap mode, it is the `k` of the prefix pass that found the overlap.
+    /// # Example
+    /// ```
+    /// use edlib_sys::{align, AlignTask, AlignMode};
+    /// let aln = align(&[b'A'; 200], &[b'C'; 200], AlignMode::Global, AlignTask::Distance);
+    /// assert_eq!(aln.dist(), 200);
+    /// assert_eq!((aln.k(), aln.rounds()), (Some(256), 3));
+    /// assert_eq!(aln.alphabet_len(), 2);
+    /// ```
+    pub fn k(&self) -> Option<u32> {
+        self.k
+    }
+    /// Return the number of passes of edlib over the target, i.e., the number of doublings of `k` plus one.
+    /// The overlap mode counts the passes over all the suffixes searched, and those of the global alignment of the overlap.
+    pub fn rounds(&self) -> u32 {
+        self.rounds
+    }
+    /// Return the number of distinct characters in the query and the target.
+    pub fn alphabet_len(&self) -> usize {
+        self.alphabet_len
+    }
+}
+
+// The number of distinct characters in the sequences.
+fn alphabet_len(query: &[u8], target: &[u8]) -> usize {
+    let mut seen = [false; 256];
+    for &c in query.iter().chain(target.iter()) {
+        seen[c as usize] = true;
+    }
+    seen.iter().filter(|&&seen| seen).count()
+}
+
+// Return the predicate of the equality, taking the additional equalities into account.
+fn equality_table(equalities: &[(u8, u8)]) -> impl Fn(u8, u8) -> bool {
+    let mut table = vec![false; 256 * 256];
+    for c in 0..256 {
+        table[c * 256 + c] = true;
+    }
+    for &(a, b) in equalities {
+        table[a as usize * 256 + b as usize] = true;
+        table[b as usize * 256 + a as usize] = true;
     }
+    move |a: u8, b: u8| table[a as usize * 256 + b as usize]
 }
 
-impl Drop for Alignment {
+impl Alignment {
+    // Copy the result of edlib, then release it.
     // Safe -- because the memory of the edlib is allocated by edlib,
     // so let it go to the edlib's deallocator.
-    fn drop(&mut self) {
-        unsafe { bindings::edlibFreeAlignResult(self.align) };
+    unsafe fn from_edlib(
+        align: bindings::EdlibAlignResult,
+        query_len: usize,
+        mode: AlignMode,
+        task: AlignTask,
+    ) -> Self {
+        let copy = |ptr: *const i32, length: i32| match ptr.is_null() {
+            true => Vec::new(),
+            false => std::slice::from_raw_parts(ptr, length as usize).to_vec(),
+        };
+        let dist = (0 <= align.editDistance).then_some(align.editDistance as u32);
+        let mut strategy = match align.alignmentStrategy as u32 {
+            bindings::EDLIB_STRATEGY_TRACEBACK => Some(TracebackStrategy::Traceback),
+            bindings::EDLIB_STRATEGY_HIRSCHBERG => Some(TracebackStrategy::Hirschberg),
+            _ => None,
+        };
+        let (mut starts, ends) = match task {
+            AlignTask::Distance => (Vec::new(), Vec::new()),
+            _ => (
+                copy(align.startLocations, align.numLocations),
+                copy(align.endLocations, align.numLocations),
+            ),
+        };
+        let mut operations = match align.alignment.is_null() {
+            true => Vec::new(),
+            false => {
+                std::slice::from_raw_parts(align.alignment, align.alignmentLength as usize).to_vec()
+            }
+        };
+        // If either sequence is empty, edlib reports neither the start locations nor the alignment.
+        // Every alignment then starts at zero, and consists of insertions and deletions only.
+        if starts.is_empty() && !ends.is_empty() {
+            starts = vec![0; ends.len()];
+            if task == AlignTask::Alignment && dist.is_some() {
+                operations = std::iter::repeat_n(1, query_len)
+                    .chain(std::iter::repeat_n(2, (ends[0] + 1) as usize))
+                    .collect();
+                strategy = Some(TracebackStrategy::Traceback);
+            }
+        }
+        bindings::edlibFreeAlignResult(align);
+        Self {
+            dist,
+            starts,
+            ends,
+            operations,
+            query_location: Some((0, query_len as i32 - 1)),
+            strategy,
+            k: (0 <= align.finalK).then_some(align.finalK as u32),
+            rounds: align.numRounds as u32,
+            alphabet_len: align.alphabetLength as usize,
+            task,
+            mode,
+        }
+    }
+}
+
+// The serialized form of an alignment, with stable field names.
+#[cfg(feature = "serde")]
+#[derive(serde::Serialize, serde::Deserialize)]
+struct SerializedAlignment {
+    mode: AlignMode,
+    task: AlignTask,
+    dist: Option<u32>,
+    locations: Vec<(i32, i32)>,
+    query_location: Option<(i32, i32)>,
+    operations: Option<String>,
+    #[serde(default)]
+    strategy: Option<TracebackStrategy>,
+    #[serde(default)]
+    k: Option<u32>,
+    #[serde(default)]
+    rounds: u32,
+    #[serde(default)]
+    alphabet_len: usize,
+}
+
+#[cfg(feature = "serde")]
+impl From<Alignment> for SerializedAlignment {
+    fn from(aln: Alignment) -> Self {
+        Self {
+            mode: aln.mode,
+            task: aln.task,
+            dist: aln.dist,
+            locations: aln
+                .starts
+                .iter()
+                .copied()
+                .zip(aln.ends.iter().copied())
+                .collect(),
+            query_location: aln.query_location,
+            operations: aln.cigar(CigarFormat::Extended),
+            strategy: aln.strategy(),
+            k: aln.k,
+            rounds: aln.rounds,
+            alphabet_len: aln.alphabet_len,
+        }
+    }
+}
+
+#[cfg(feature = "serde")]
+impl std::convert::TryFrom<SerializedAlignment> for Alignment {
+    type Error = String;
+    fn try_from(aln: SerializedAlignment) -> Result<Self, Self::Error> {
+        let operations = match aln.operations.as_deref() {
+            Some(cigar) => parse_extended_cigar(cigar)?,
+            None => Vec::new(),
+        };
+        let (starts, ends) = aln.locations.into_iter().unzip();
+        Ok(Self {
+            dist: aln.dist,
+            starts,
+            ends,
+            operations,
+            query_location: aln.query_location,
+            strategy: aln.strategy,
+            k: aln.k,
+            rounds: aln.rounds,
+            alphabet_len: aln.alphabet_len,
+            task: aln.task,
+            mode: aln.mode,
+        })
+    }
+}
+
+// Inverse of `cigar(ops, CigarFormat::Extended)`.
+#[cfg(feature = "serde")]
+fn parse_extended_cigar(cigar: &str) -> Result<Vec<u8>, String> {
+    let mut ops = Vec::new();
+    let mut length = 0usize;
+    for c in cigar.chars() {
+        if let Some(digit) = c.to_digit(10) {
+            length = length * 10 + digit as usize;
+            continue;
+        }
+        let op = match c {
+            '=' => 0,
+            'I' => 1,
+            'D' => 2,
+            'X' => 3,
+            _ => return Err(format!("Invalid CIGAR operation:{}", c)),
+        };
+        ops.extend(std::iter::repeat_n(op, length));
+        length = 0;
+    }
+    match length {
+        0 => Ok(ops),
+        _ => Err(format!("Trailing length in CIGAR:{}", cigar)),
     }
 }
 
@@ -181,11 +480,33 @@ impl Drop for Alignment {
 /// - Global: Align entire sequence between `target` and `query`. Corresponds to `EdlibAlignMode_EDLIB_MODE_NW`.
 /// - Prefix: Align the prefix of the `target` and `query`. Corresponds to `EdlibAlignMode_EDLIB_MODE_SHW`
 /// - Infix: Align the `query` into the substring of the `target`. Corresnponds to `EdlibAlignMode_EDLIB_MODE_HW`
-#[derive(Debug, Clone, Copy)]
+/// - Suffix: Align the `query` to a suffix of the `target`. Computed by `EdlibAlignMode_EDLIB_MODE_SHW` on the reversed sequences.
+/// - Overlap: Align a suffix of one sequence to a prefix of the other, with free end gaps on both sequences.
+///   Use [`Alignment::query_location()`] together with [`Alignment::location()`] to obtain the overlap.
+///   As every sequence pair has an empty overlap with no edits, the overlap maximizing
+///   `Lq + Lt - 4 * dist` is reported, where `Lq` and `Lt` are the lengths of the overlapping
+///   segments of the query and the target. In other words, each edit should be paid by two bases of the overlap.
+///   Each suffix of either sequence is aligned to the prefixes of the other by `EdlibAlignMode_EDLIB_MODE_SHW`,
+///   which finds the prefix at the minimal distance (the longest one if tied), with `k` tightened by the best score so far;
+///   thus a longer prefix at a larger distance is not considered even if it scores better.
+///   The search stops at the suffixes too short to score better, and the overlap is aligned by `EdlibAlignMode_EDLIB_MODE_NW`.
+///   With `k`, only the overlaps within `k` edits are searched.
+///   If there is no such overlap at all, the alignment is not found.
+///   Note that it does not report containment -- use `Infix` for that.
+/// - Local: Align a substring of the `query` to a substring of the `target`.
+///   The pair of substrings maximizing `matches - edits` is found by a Smith-Waterman dynamic programming in Rust,
+///   taking `O(|query| * |target|)` time, then aligned by `EdlibAlignMode_EDLIB_MODE_NW`.
+///   The distance is the edit distance between the substrings.
+///   If no base matches, i.e., the best score is zero, the alignment is not found, as in the overlap mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AlignMode {
     Global,
     Prefix,
     Infix,
+    Suffix,
+    Overlap,
+    Local,
 }
 
 /// The alignment task to be used.
@@ -193,12 +514,318 @@ pub enum AlignMode {
 /// - Location: Distance & the location on the `target` squence would be computed.
 /// - Alignment: Distance, the location, and the alignment operations would be computed.
 #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum AlignTask {
     Distance,
     Location,
     Alignment,
 }
 
+/// How the alignment operations were computed by edlib.
+/// - Traceback: The whole traceback data was kept in memory, as it fitted in the memory budget.
+/// - Hirschberg: The problem was recursively split in halves by Hirschberg's algorithm, until
+///   the traceback data of each subproblem fitted in the memory budget. It needs less memory but is slower.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TracebackStrategy {
+    Traceback,
+    Hirschberg,
+}
+
+/// The format of CIGAR strings.
+/// - Standard: Matches and mismatches are both `M`. Insertions are `I` and deletions are `D`.
+/// - Extended: Matches are `=` and mismatches are `X`. Insertions are `I` and deletions are `D`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CigarFormat {
+    Standard,
+    Extended,
+}
+
+/// Convert alignment operations (see [`Alignment::operations()`]) into a CIGAR string.
+pub fn cigar(ops: &[u8], format: CigarFormat) -> String {
+    let symbol = |op: u8| match (op, format) {
+        (0, CigarFormat::Standard) | (3, CigarFormat::Standard) => 'M',
+        (0, CigarFormat::Extended) => '=',
+        (3, CigarFormat::Extended) => 'X',
+        (1, _) => 'I',
+        (2, _) => 'D',
+        _ => panic!("Invalid alignment operation:{}", op),
+    };
+    let mut cigar = String::new();
+    let mut ops = ops.iter().map(|&op| symbol(op)).peekable();
+    while let Some(op) = ops.next() {
+        let mut length = 1;
+        while ops.next_if_eq(&op).is_some() {
+            length += 1;
+        }
+        cigar.push_str(&format!("{}{}", length, op));
+    }
+    cigar
+}
+
+/// The reason why an alignment was not computed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlignError {
+    /// The alignment was cancelled by a [`CancelToken`], or passed the deadline of the configuration.
+    Cancelled,
+    /// The sequences have more distinct symbols than the 256 edlib can handle (see [`align_symbols`]).
+    TooManySymbols(usize),
+    /// The alignment mode is not supported by the aligner, such as [`AlignMode::Local`] in [`align_hamming`].
+    UnsupportedMode(AlignMode),
+}
+
+impl std::fmt::Display for AlignError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Cancelled => write!(f, "the alignment was cancelled"),
+            Self::TooManySymbols(found) => {
+                write!(
+                    f,
+                    "{} distinct symbols, while at most 256 are supported",
+                    found
+                )
+            }
+            Self::UnsupportedMode(mode) => write!(f, "the {:?} mode is not supported", mode),
+        }
+    }
+}
+
+impl std::error::Error for AlignError {}
+
+/// A token to cancel alignments, possibly from another thread (see [`AlignConfig::with_cancel_token`]).
+/// The clones of a token share the same state, and are equal to each other.
+#[derive(Debug, Clone, Default)]
+pub struct CancelToken(std::sync::Arc<std::sync::atomic::AtomicBool>);
+
+impl CancelToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    /// Cancel the alignments using this token, both running and future ones.
+    pub fn cancel(&self) {
+        self.0.store(true, std::sync::atomic::Ordering::Relaxed);
+    }
+    pub fn is_cancelled(&self) -> bool {
+        self.0.load(std::sync::atomic::Ordering::Relaxed)
+    }
+}
+
+impl PartialEq for CancelToken {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for CancelToken {}
+
+/// The progress of an alignment, given to the callback of [`AlignConfig::with_progress`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Progress {
+    fraction: f64,
+    k: Option<u32>,
+}
+
+impl Progress {
+    /// Return the fraction of the current pass processed, from 0 to 1.
+    /// A pass of edlib processes the target column by column.
+    pub fn fraction(&self) -> f64 {
+        self.fraction
+    }
+    /// Return the upper bound of the distance in the current pass of edlib.
+    /// Without [`AlignConfig::with_k`], the target is processed again with a doubled `k` until the alignment is found.
+    /// None for the passes done outside of edlib, such as the dynamic programming of the local mode.
+    pub fn k(&self) -> Option<u32> {
+        self.k
+    }
+}
+
+// A progress callback, compared by identity.
+#[derive(Clone)]
+struct ProgressFn(std::sync::Arc<dyn Fn(Progress) + Send + Sync>);
+
+impl std::fmt::Debug for ProgressFn {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("ProgressFn")
+    }
+}
+
+impl PartialEq for ProgressFn {
+    fn eq(&self, other: &Self) -> bool {
+        std::sync::Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for ProgressFn {}
+
+// The minimum interval between two progress reports in the middle of a pass.
+const PROGRESS_INTERVAL: std::time::Duration = std::time::Duration::from_millis(100);
+
+// Report the progress of an alignment under the configuration, at a bounded rate.
+// It is also given to the callbacks of edlib.
+struct Reporter<'a> {
+    config: &'a AlignConfig,
+    last: std::cell::Cell<Option<std::time::Instant>>,
+}
+
+impl<'a> Reporter<'a> {
+    fn new(config: &'a AlignConfig) -> Self {
+        Self {
+            config,
+            last: std::cell::Cell::new(None),
+        }
+    }
+    // Report the start and the end of a pass, and the rest at most every `PROGRESS_INTERVAL`.
+    fn report(&self, fraction: f64, k: Option<u32>) {
+        let callback = match self.config.progress.as_ref() {
+            Some(callback) => callback,
+            None => return,
+        };
+        let now = std::time::Instant::now();
+        let is_boundary = fraction <= 0.0 || 1.0 <= fraction;
+        if is_boundary
+            || self
+                .last
+                .get()
+                .is_none_or(|last| last + PROGRESS_INTERVAL <= now)
+        {
+            self.last.set(Some(now));
+            (callback.0)(Progress { fraction, k });
+        }
+    }
+}
+
+/// The configuration of an alignment. Use [`align_with`] to align sequences under the configuration.
+/// # Example
+/// ```
+/// use edlib_sys::{align_with, AlignConfig, AlignTask, AlignMode};
+/// let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance).with_k(1);
+/// assert_eq!(align_with(b"ACGT", b"ACCT", &config).dist(), 1);
+/// assert!(!align_with(b"ACGT", b"TTTT", &config).is_found());
+/// // Let `N` match to any base.
+/// let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance)
+///     .with_equalities(&[(b'N', b'A'), (b'N', b'C'), (b'N', b'G'), (b'N', b'T')]);
+/// assert_eq!(align_with(b"ACNT", b"ACGT", &config).dist(), 0);
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct AlignConfig {
+    mode: AlignMode,
+    task: AlignTask,
+    k: Option<u32>,
+    equalities: Vec<(u8, u8)>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    memory_budget: Option<usize>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    cancel_token: Option<CancelToken>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    deadline: Option<std::time::Instant>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    progress: Option<ProgressFn>,
+}
+
+impl AlignConfig {
+    /// Create a configuration without the upper bound of the distance and additional equalities.
+    pub fn new(mode: AlignMode, task: AlignTask) -> Self {
+        Self {
+            mode,
+            task,
+            k: None,
+            equalities: Vec::new(),
+            memory_budget: None,
+            cancel_token: None,
+            deadline: None,
+            progress: None,
+        }
+    }
+    /// Set the upper bound of the edit distance.
+    /// A small `k` can significantly speed up the computation.
+    /// If the distance is larger than `k`, the alignment is reported as not found (see [`Alignment::is_found()`]).
+    pub fn with_k(mut self, k: u32) -> Self {
+        self.k = Some(k);
+        self
+    }
+    /// Treat each pair of characters as equal, in addition to the identical characters.
+    pub fn with_equalities(mut self, equalities: &[(u8, u8)]) -> Self {
+        self.equalities.extend_from_slice(equalities);
+        self
+    }
+    /// Set the maximum size (in bytes) of the traceback data kept in memory to compute the alignment operations.
+    /// If more is needed, Hirschberg's algorithm is used to split the problem, which needs less memory but is slower.
+    /// The default is 1 MB. See [`Alignment::strategy()`].
+    pub fn with_memory_budget(mut self, bytes: usize) -> Self {
+        self.memory_budget = Some(bytes);
+        self
+    }
+    /// Stop the alignment once the `token` is cancelled. See [`try_align_with`].
+    /// The token is not serialized.
+    pub fn with_cancel_token(mut self, token: CancelToken) -> Self {
+        self.cancel_token = Some(token);
+        self
+    }
+    /// Stop the alignment once the `deadline` has passed. See [`try_align_with`].
+    /// The deadline is not serialized.
+    pub fn with_deadline(mut self, deadline: std::time::Instant) -> Self {
+        self.deadline = Some(deadline);
+        self
+    }
+    /// Stop the alignment after `timeout` from now. See [`AlignConfig::with_deadline`].
+    pub fn with_timeout(self, timeout: std::time::Duration) -> Self {
+        self.with_deadline(std::time::Instant::now() + timeout)
+    }
+    /// Call `callback` with the progress of the alignment, at the start and the end of each pass,
+    /// and at most every 100 milliseconds in between. See [`Progress`].
+    /// The callback is called on the aligning thread, and must not panic.
+    /// The overlap mode reports the fraction of the suffixes searched together with the `k` of each of them, instead of
+    /// the passes of edlib.
+    /// The callback is not serialized.
+    pub fn with_progress(mut self, callback: impl Fn(Progress) + Send + Sync + 'static) -> Self {
+        self.progress = Some(ProgressFn(std::sync::Arc::new(callback)));
+        self
+    }
+    pub fn mode(&self) -> AlignMode {
+        self.mode
+    }
+    pub fn task(&self) -> AlignTask {
+        self.task
+    }
+    pub fn k(&self) -> Option<u32> {
+        self.k
+    }
+    pub fn equalities(&self) -> &[(u8, u8)] {
+        &self.equalities
+    }
+    pub fn memory_budget(&self) -> Option<usize> {
+        self.memory_budget
+    }
+    pub fn cancel_token(&self) -> Option<&CancelToken> {
+        self.cancel_token.as_ref()
+    }
+    pub fn deadline(&self) -> Option<std::time::Instant> {
+        self.deadline
+    }
+    // Return true if the alignment may be stopped by a cancel token or a deadline.
+    fn is_cancellable(&self) -> bool {
+        self.cancel_token.is_some() || self.deadline.is_some()
+    }
+    // Return true if the alignment should be stopped.
+    fn is_cancelled(&self) -> bool {
+        self.cancel_token
+            .as_ref()
+            .is_some_and(CancelToken::is_cancelled)
+            || self
+                .deadline
+                .is_some_and(|deadline| deadline <= std::time::Instant::now())
+    }
+    // The same configuration with a different mode and task.
+    fn with_mode_task(&self, mode: AlignMode, task: AlignTask) -> Self {
+        Self {
+            mode,
+            task,
+            ..self.clone()
+        }
+    }
+}
+
 /// Align the `query` to the `target` sequence.
 /// # Example
 /// ```
@@ -230,30 +857,147 @@ pub enum AlignTask {
 /// assert_eq!(aln.operations(), Some([2, 0, 0, 0, 0, 1, 0].as_slice()));
 /// ```
 pub fn align(query: &[u8], target: &[u8], mode: AlignMode, task: AlignTask) -> Alignment {
+    align_with(query, target, &AlignConfig::new(mode, task))
+}
+
+/// Align the `query` to the `target` sequence under the `config`.
+/// See [`AlignConfig`] for an example.
+/// # Panics
+/// Panics if the alignment is cancelled. Use [`try_align_with`] for the configurations with a
+/// cancel token or a deadline.
+pub fn align_with(query: &[u8], target: &[u8], config: &AlignConfig) -> Alignment {
+    try_align_with(query, target, config).unwrap_or_else(|why| panic!("{}", why))
+}
+
+// Align under a configuration built in this crate, which is rejected if it has a cancel token or a deadline,
+// thus the alignment is never cancelled.
+fn align_uncancellable(query: &[u8], target: &[u8], config: &AlignConfig) -> Alignment {
+    assert!(
+        !config.is_cancellable(),
+        "the configuration should not be cancellable"
+    );
+    match try_align_with(query, target, config) {
+        Ok(aln) => aln,
+        Err(why) => unreachable!("{}", why),
+    }
+}
+
+/// Align the `query` to the `target` sequence under the `config`, or return [`AlignError::Cancelled`]
+/// if the cancel token of the `config` is cancelled or its deadline passes before the alignment finishes.
+/// The alignment loops check them periodically, around every millisecond.
+/// # Example
+/// ```
+/// use edlib_sys::{try_align_with, AlignConfig, AlignError, AlignMode, AlignTask, CancelToken};
+/// use std::time::Duration;
+/// let token = CancelToken::new();
+/// let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment)
+///     .with_cancel_token(token.clone())
+///     .with_timeout(Duration::from_secs(10));
+/// assert_eq!(try_align_with(b"ACGT", b"ACCT", &config).unwrap().dist(), 1);
+/// token.cancel();
+/// assert_eq!(try_align_with(b"ACGT", b"ACCT", &config), Err(AlignError::Cancelled));
+/// ```
+pub fn try_align_with(
+    query: &[u8],
+    target: &[u8],
+    config: &AlignConfig,
+) -> Result<Alignment, AlignError> {
+    if config.is_cancelled() {
+        return Err(AlignError::Cancelled);
+    }
+    match config.mode {
+        AlignMode::Suffix => overlap::align_suffix(query, target, config),
+        AlignMode::Overlap => overlap::align_overlap(query, target, config),
+        AlignMode::Local => local::align_local(query, target, config),
+        _ => edlib_align(query, target, config),
+    }
+}
+
+// Called by edlib periodically with the reporter.
+unsafe extern "C" fn should_cancel(reporter: *mut std::os::raw::c_void) -> std::os::raw::c_int {
+    let reporter = &*(reporter as *const Reporter<'_>);
+    reporter.config.is_cancelled() as std::os::raw::c_int
+}
+
+// Called by edlib periodically during the passes with the reporter.
+unsafe extern "C" fn report_progress(
+    reporter: *mut std::os::raw::c_void,
+    progress: f64,
+    k: std::os::raw::c_int,
+) {
+    let reporter = &*(reporter as *const Reporter<'_>);
+    reporter.report(progress, Some(k as u32));
+}
+
+// Call edlib. The mode should be one of `Global`, `Prefix`, and `Infix`.
+fn edlib_align(query: &[u8], target: &[u8], config: &AlignConfig) -> Result<Alignment, AlignError> {
+    let (mode, task) = (config.mode, config.task);
     let mode_ed = match mode {
         AlignMode::Global => bindings::EdlibAlignMode_EDLIB_MODE_NW,
         AlignMode::Prefix => bindings::EdlibAlignMode_EDLIB_MODE_SHW,
         AlignMode::Infix => bindings::EdlibAlignMode_EDLIB_MODE_HW,
+        _ => panic!("{:?} is not supported by edlib", mode),
     };
     let task_ed = match task {
         AlignTask::Distance => bindings::EdlibAlignTask_EDLIB_TASK_DISTANCE,
         AlignTask::Location => bindings::EdlibAlignTask_EDLIB_TASK_LOC,
         AlignTask::Alignment => bindings::EdlibAlignTask_EDLIB_TASK_PATH,
     };
+    let k = config
+        .k
+        .map(|k| k.min(i32::MAX as u32) as i32)
+        .unwrap_or(-1);
+    // Edlib uses the default for non-positive budgets, while zero should mean no traceback data at all.
+    let memory_budget = config
+        .memory_budget
+        .map_or(-1, |bytes| bytes.clamp(1, i64::MAX as usize) as i64);
+    let mut equalities: Vec<_> = config
+        .equalities
+        .iter()
+        .map(|&(first, second)| bindings::EdlibEqualityPair {
+            first: first as std::os::raw::c_char,
+            second: second as std::os::raw::c_char,
+        })
+        .collect();
+    let cancellable = config.is_cancellable();
+    let reports = config.progress.is_some();
+    let reporter = Reporter::new(config);
+    let reporter = &reporter as *const Reporter<'_> as *mut std::os::raw::c_void;
     unsafe {
-        let config = bindings::edlibNewAlignConfig(-1, mode_ed, task_ed, std::ptr::null_mut(), 0);
+        let mut config = bindings::edlibNewAlignConfig(
+            k,
+            mode_ed,
+            task_ed,
+            equalities.as_mut_ptr(),
+            equalities.len() as i32,
+        );
+        config.memoryBudget = memory_budget;
+        if cancellable {
+            config.shouldCancel = Some(should_cancel);
+            config.cancelData = reporter;
+        }
+        if reports {
+            config.reportProgress = Some(report_progress);
+            config.progressData = reporter;
+        }
         let align = bindings::edlibAlign(
-            query.as_ptr() as *const i8,
+            query.as_ptr() as *const std::os::raw::c_char,
             query.len() as i32,
-            target.as_ptr() as *const i8,
+            target.as_ptr() as *const std::os::raw::c_char,
             target.len() as i32,
             config,
         );
-        if align.status != 0 {
-            panic!("Edlib paniced!");
-        } else {
-            Alignment { align, task, mode }
+        match align.status as u32 {
+            bindings::EDLIB_STATUS_OK => {}
+            bindings::EDLIB_STATUS_CANCELLED => return Err(AlignError::Cancelled),
+            _ => panic!("Edlib paniced!"),
         }
+        let mut aln = Alignment::from_edlib(align, query.len(), mode, task);
+        // Edlib does not check `k` if either sequence is empty.
+        if aln.dist.is_some_and(|dist| 0 <= k && k < dist as i32) {
+            aln.dist = None;
+        }
+        Ok(aln)
     }
 }
 
@@ -270,4 +1014,192 @@ mod test {
         assert_eq!(aln.dist(), 0);
         assert_eq!(aln.operations(), Some([0, 0, 0].as_slice()));
     }
+    #[test]
+    fn stats_test() {
+        let task = AlignTask::Alignment;
+        let config = crate::AlignConfig::new(AlignMode::Prefix, task).with_k(1);
+        let aln = crate::align_with(b"ACGT", b"ACCTTT", &config);
+        assert_eq!((aln.dist(), aln.k(), aln.rounds()), (1, Some(1), 1));
+        assert_eq!(aln.alphabet_len(), 4);
+        // No pass is run for empty sequences.
+        let aln = align(b"", b"AC", AlignMode::Global, task);
+        assert_eq!((aln.k(), aln.rounds(), aln.alphabet_len()), (None, 0, 2));
+    }
+    #[test]
+    fn memory_budget_test() {
+        let task = AlignTask::Alignment;
+        // About 300 KB of traceback data is needed.
+        let query: Vec<u8> = (0..1000u32)
+            .map(|i| b"ACGT"[(i * i % 7 % 4) as usize])
+            .collect();
+        let target: Vec<u8> = query.iter().rev().copied().collect();
+        let config = crate::AlignConfig::new(AlignMode::Global, task);
+        let traceback = crate::align_with(&query, &target, &config);
+        assert_eq!(
+            traceback.strategy(),
+            Some(crate::TracebackStrategy::Traceback)
+        );
+        let config = config.with_memory_budget(100_000);
+        let hirschberg = crate::align_with(&query, &target, &config);
+        assert_eq!(
+            hirschberg.strategy(),
+            Some(crate::TracebackStrategy::Hirschberg)
+        );
+        assert_eq!(hirschberg.dist(), traceback.dist());
+        assert_eq!(
+            crate::edit::validate(&query, &target, &hirschberg, &[]),
+            Ok(())
+        );
+    }
+    #[test]
+    fn empty_test() {
+        // Edlib handles empty sequences separately.
+        let task = AlignTask::Alignment;
+        let aln = align(b"", b"AC", AlignMode::Global, task);
+        assert_eq!(aln.location(), Some((0, 1)));
+        assert_eq!(aln.operations(), Some([2, 2].as_slice()));
+        let config = crate::AlignConfig::new(AlignMode::Prefix, task).with_k(1);
+        assert!(!crate::align_with(b"AC", b"", &config).is_found());
+    }
+    #[test]
+    fn cancel_test() {
+        use crate::{try_align_with, AlignConfig, AlignError, CancelToken};
+        use std::time::Instant;
+        // Unrelated sequences, which take seconds to align.
+        let random = |seed: u64, len: usize| -> Vec<u8> {
+            let mut x = seed;
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    b"ACGT"[(x >> 62) as usize]
+                })
+                .collect()
+        };
+        let (query, target) = (random(1, 200_000), random(2, 200_000));
+        // Cancel the alignment from the progress callback once `fraction` is reported.
+        let cancel_at = |mode: AlignMode, task: AlignTask, fraction: f64| {
+            let token = CancelToken::new();
+            let canceller = token.clone();
+            AlignConfig::new(mode, task)
+                .with_cancel_token(token)
+                .with_progress(move |progress| {
+                    if fraction <= progress.fraction() {
+                        canceller.cancel();
+                    }
+                })
+        };
+        let config = cancel_at(AlignMode::Global, AlignTask::Distance, 0.0);
+        let result = try_align_with(&query, &target, &config);
+        assert_eq!(result, Err(AlignError::Cancelled));
+        // The alignment is cancelled while finding the start locations or the path, after the first pass.
+        for mode in [AlignMode::Infix, AlignMode::Suffix] {
+            let config = cancel_at(mode, AlignTask::Alignment, 1.0);
+            let result = try_align_with(&query[..50_000], &target, &config);
+            assert_eq!(result, Err(AlignError::Cancelled), "{:?}", mode);
+        }
+        // The overlap mode is cancelled during its search over the suffixes.
+        let config = cancel_at(AlignMode::Overlap, AlignTask::Alignment, 0.0);
+        let result = try_align_with(&query[..10_000], &target[..10_000], &config);
+        assert_eq!(result, Err(AlignError::Cancelled));
+        // Nothing is computed after the deadline.
+        let config =
+            AlignConfig::new(AlignMode::Local, AlignTask::Distance).with_deadline(Instant::now());
+        assert_eq!(
+            try_align_with(b"ACGT", b"ACGT", &config),
+            Err(AlignError::Cancelled)
+        );
+    }
+    #[test]
+    fn progress_test() {
+        use crate::{align_with, AlignConfig, Progress};
+        use std::sync::{Arc, Mutex};
+        let query: Vec<u8> = (0..20_000u32)
+            .map(|i| b"ACGT"[(i * i % 7 % 4) as usize])
+            .collect();
+        let target: Vec<u8> = query.iter().rev().copied().collect();
+        let reports: Arc<Mutex<Vec<Progress>>> = Arc::default();
+        let sink = reports.clone();
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance)
+            .with_progress(move |progress| sink.lock().unwrap().push(progress));
+        let aln = align_with(&query, &target, &config);
+        let reports = reports.lock().unwrap();
+        // Each pass is reported from the start to the end, doubling `k` from 64.
+        assert_eq!(reports[0].fraction(), 0.0);
+        assert_eq!(reports[0].k(), Some(64));
+        let last = reports.last().unwrap();
+        assert_eq!(last.fraction(), 1.0);
+        assert!(aln.dist() <= last.k().unwrap());
+        for pair in reports.windows(2) {
+            match pair[0].k() == pair[1].k() {
+                true => assert!(pair[0].fraction() <= pair[1].fraction()),
+                false => {
+                    assert_eq!(pair[0].fraction(), 1.0);
+                    assert_eq!(pair[1].fraction(), 0.0);
+                    assert_eq!(pair[1].k(), pair[0].k().map(|k| 2 * k));
+                }
+            }
+        }
+        // The local mode reports its dynamic programming as well.
+        let reports: Arc<Mutex<Vec<Progress>>> = Arc::default();
+        let sink = reports.clone();
+        let config = AlignConfig::new(AlignMode::Local, AlignTask::Distance)
+            .with_progress(move |progress| sink.lock().unwrap().push(progress));
+        align_with(&query[..1000], &target[..1000], &config);
+        let reports = reports.lock().unwrap();
+        assert_eq!((reports[0].fraction(), reports[0].k()), (0.0, None));
+        assert!(reports
+            .iter()
+            .any(|p| p.fraction() == 1.0 && p.k().is_none()));
+    }
+    #[cfg(feature = "serde")]
+    #[test]
+    fn serde_test() {
+        let target = b"ACGTCGTACGTTTACG";
+        let query = b"CGTCCGTACTTT";
+        for task in [
+            AlignTask::Distance,
+            AlignTask::Location,
+            AlignTask::Alignment,
+        ] {
+            let aln = align(query, target, AlignMode::Infix, task);
+            let json = serde_json::to_string(&aln).unwrap();
+            assert_eq!(
+                serde_json::from_str::<crate::Alignment>(&json).unwrap(),
+                aln
+            );
+            let config = bincode::config::standard();
+            let bytes = bincode::serde::encode_to_vec(&aln, config).unwrap();
+            let (decoded, _): (crate::Alignment, _) =
+                bincode::serde::decode_from_slice(&bytes, config).unwrap();
+            assert_eq!(decoded, aln);
+        }
+        let aln = align(
+            b"CGTCCG",
+            b"ACGTCGT",
+            AlignMode::Prefix,
+            AlignTask::Alignment,
+        );
+        let json = serde_json::to_value(&aln).unwrap();
+        let expected = serde_json::json!({
+            "mode": "Prefix",
+            "task": "Alignment",
+            "dist": 2,
+            "locations": [[0, 5]],
+            "query_location": [0, 5],
+            "operations": "1D4=1I1=",
+            "strategy": "Traceback",
+            "k": 64,
+            "rounds": 1,
+            "alphabet_len": 4,
+        });
+        assert_eq!(json, expected);
+        let config = crate::AlignConfig::new(AlignMode::Global, AlignTask::Distance).with_k(3);
+        let json = serde_json::to_string(&config).unwrap();
+        assert_eq!(
+            serde_json::from_str::<crate::AlignConfig>(&json).unwrap(),
+            config
+        );
+    }
 }
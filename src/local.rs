@@ -0,0 +1,192 @@
+//! Local alignment ([`AlignMode::Local`]).
+//!
+//! Edlib does not have a local alignment mode, so the best pair of substrings is found by a plain
+//! Smith-Waterman dynamic programming with the match score +1 and the mismatch/insertion/deletion score -1,
+//! i.e., the pair maximizing `matches - edits`. Then, edlib aligns the substrings globally.
+use crate::{
+    alphabet_len, edlib_align, equality_table, AlignConfig, AlignError, AlignMode, Alignment,
+    Reporter,
+};
+
+// A cell of the DP table: the score and where the local alignment ending at the cell starts.
+#[derive(Debug, Clone, Copy, Default)]
+struct Cell {
+    score: i64,
+    qstart: usize,
+    tstart: usize,
+}
+
+impl Cell {
+    fn extend(&self, score: i64) -> Self {
+        Self {
+            score: self.score + score,
+            ..*self
+        }
+    }
+}
+
+/// Find the pair of substrings maximizing `matches - edits`, then align them globally.
+pub(crate) fn align_local(
+    query: &[u8],
+    target: &[u8],
+    config: &AlignConfig,
+) -> Result<Alignment, AlignError> {
+    let is_equal = equality_table(config.equalities());
+    let reporter = Reporter::new(config);
+    let mut prev = vec![Cell::default(); target.len() + 1];
+    let mut current = vec![Cell::default(); target.len() + 1];
+    // (score, query range, target range), where the ranges are half-open.
+    let mut best = (0, (0, 0), (0, 0));
+    for (i, &q) in query.iter().enumerate() {
+        if config.is_cancelled() {
+            return Err(AlignError::Cancelled);
+        }
+        reporter.report(i as f64 / query.len() as f64, None);
+        current[0] = Cell::default();
+        for (j, &t) in target.iter().enumerate() {
+            let diag = match is_equal(q, t) {
+                true => prev[j].extend(1),
+                false => prev[j].extend(-1),
+            };
+            let diag = match prev[j].score {
+                0 => Cell {
+                    qstart: i,
+                    tstart: j,
+                    ..diag
+                },
+                _ => diag,
+            };
+            let cell =
+                [prev[j + 1].extend(-1), current[j].extend(-1)]
+                    .iter()
+                    .fold(diag, |best, &cell| match best.score < cell.score {
+                        true => cell,
+                        false => best,
+                    });
+            current[j + 1] = match cell.score <= 0 {
+                true => Cell::default(),
+                false => cell,
+            };
+            if best.0 < cell.score {
+                best = (cell.score, (cell.qstart, i + 1), (cell.tstart, j + 1));
+            }
+        }
+        std::mem::swap(&mut prev, &mut current);
+    }
+    reporter.report(1.0, None);
+    let (score, (qstart, qend), (tstart, tend)) = best;
+    // Without any matching base, the alignment is not found.
+    if score == 0 {
+        return Ok(Alignment {
+            dist: None,
+            starts: Vec::new(),
+            ends: Vec::new(),
+            operations: Vec::new(),
+            query_location: None,
+            strategy: None,
+            k: None,
+            rounds: 0,
+            alphabet_len: alphabet_len(query, target),
+            task: config.task,
+            mode: AlignMode::Local,
+        });
+    }
+    let global = config.with_mode_task(AlignMode::Global, config.task);
+    let mut aln = edlib_align(&query[qstart..qend], &target[tstart..tend], &global)?;
+    aln.starts = vec![tstart as i32];
+    aln.ends = vec![tend as i32 - 1];
+    aln.query_location = Some((qstart as i32, qend as i32 - 1));
+    aln.alphabet_len = alphabet_len(query, target);
+    aln.mode = AlignMode::Local;
+    Ok(aln)
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{align, AlignMode, AlignTask};
+    #[test]
+    fn local_test() {
+        let query = b"TTTTTTTTACGTACGTACGTGGGGGGGG";
+        let target = b"CCCCCACGTACCTACGTAAAAAA";
+        let aln = align(query, target, AlignMode::Local, AlignTask::Alignment);
+        assert_eq!(aln.dist(), 1);
+        assert_eq!(aln.query_location(), Some((8, 19)));
+        assert_eq!(aln.location(), Some((5, 16)));
+        assert_eq!(aln.operations().unwrap().len(), 12);
+        // Without any matching base, the alignment is not found.
+        let aln = align(b"AAAA", b"CCCC", AlignMode::Local, AlignTask::Location);
+        assert!(!aln.is_found());
+        assert_eq!(aln.location(), None);
+        assert_eq!(aln.query_location(), None);
+        let aln = align(b"", b"ACGT", AlignMode::Local, AlignTask::Distance);
+        assert!(!aln.is_found());
+    }
+    // The scores of the plain Smith-Waterman dynamic programming: `table[i][j]` is the best score
+    // of the local alignments ending at `query[i - 1]` and `target[j - 1]`.
+    fn smith_waterman(query: &[u8], target: &[u8]) -> Vec<Vec<i64>> {
+        let mut table = vec![vec![0i64; target.len() + 1]; query.len() + 1];
+        for i in 1..=query.len() {
+            for j in 1..=target.len() {
+                let diag = match query[i - 1] == target[j - 1] {
+                    true => 1,
+                    false => -1,
+                };
+                table[i][j] = (table[i - 1][j - 1] + diag)
+                    .max(table[i - 1][j] - 1)
+                    .max(table[i][j - 1] - 1)
+                    .max(0);
+            }
+        }
+        table
+    }
+    #[test]
+    fn smith_waterman_test() {
+        let mut x = 30u64;
+        let mut random = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    b"ACGT"[(x >> 62) as usize]
+                })
+                .collect()
+        };
+        for _ in 0..20 {
+            // A shared segment with a few edits, surrounded by unrelated sequences.
+            let shared = random(40);
+            let mut mutated = shared.clone();
+            for (i, &c) in random(3).iter().enumerate() {
+                mutated[10 * i + 5] = c;
+            }
+            mutated.remove(30);
+            let query = [random(20), shared, random(20)].concat();
+            let target = [random(30), mutated, random(10)].concat();
+            let aln = align(&query, &target, AlignMode::Local, AlignTask::Alignment);
+            // The best score and the cell reaching it first, row by row.
+            let table = smith_waterman(&query, &target);
+            let (mut best, mut best_end) = (0, (0, 0));
+            for (i, row) in table.iter().enumerate() {
+                for (j, &score) in row.iter().enumerate() {
+                    if best < score {
+                        best = score;
+                        best_end = (i - 1, j - 1);
+                    }
+                }
+            }
+            let (qs, qe) = aln.query_location().unwrap();
+            let (ts, te) = aln.location().unwrap();
+            assert_eq!((qe, te), best_end);
+            // The reported substrings score the best as a whole.
+            let table = smith_waterman(&query[qs..=qe], &target[ts..=te]);
+            assert_eq!(table[qe - qs + 1][te - ts + 1], best);
+            let global = align(
+                &query[qs..=qe],
+                &target[ts..=te],
+                AlignMode::Global,
+                AlignTask::Distance,
+            );
+            assert_eq!(global.dist(), aln.dist());
+        }
+    }
+}
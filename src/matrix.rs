@@ -0,0 +1,164 @@
+//! # All-vs-all distance matrix
+//!
+//! Compute the edit distances between all the pairs of a set of sequences, in parallel.
+//! The result is kept as a condensed upper-triangular matrix, in the same order as `scipy.spatial.distance.pdist`,
+//! so it can be passed to hierarchical clustering as it is.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::matrix::distance_matrix;
+//! use edlib_sys::AlignMode;
+//! let seqs: Vec<&[u8]> = vec![b"ACGT", b"ACCT", b"TTTTTTTT"];
+//! let matrix = distance_matrix(&seqs, AlignMode::Global, Some(2));
+//! assert_eq!(matrix.get(0, 1), Some(1));
+//! assert_eq!(matrix.get(1, 0), Some(1));
+//! // The distance exceeds the cap.
+//! assert_eq!(matrix.get(0, 2), None);
+//! assert_eq!(matrix.condensed(), [Some(1), None, None].as_slice());
+//! let phylip = matrix.to_phylip(&["a", "b", "c"]);
+//! assert_eq!(phylip.lines().next(), Some("3"));
+//! ```
+use crate::{align_uncancellable, AlignConfig, AlignMode, AlignTask};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+/// A symmetric distance matrix with zero diagonal.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DistanceMatrix {
+    len: usize,
+    max_k: Option<u32>,
+    condensed: Vec<Option<u32>>,
+}
+
+impl DistanceMatrix {
+    /// Return the number of the sequences.
+    pub fn len(&self) -> usize {
+        self.len
+    }
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+    // The index of (i, j) in the condensed matrix. `i < j` should hold.
+    fn index(&self, i: usize, j: usize) -> usize {
+        self.len * i - i * (i + 1) / 2 + j - i - 1
+    }
+    /// Return the distance between the `i`-th and the `j`-th sequences.
+    /// Return None if the distance is larger than the cap.
+    pub fn get(&self, i: usize, j: usize) -> Option<u32> {
+        match i.cmp(&j) {
+            std::cmp::Ordering::Less => self.condensed[self.index(i, j)],
+            std::cmp::Ordering::Equal => Some(0),
+            std::cmp::Ordering::Greater => self.condensed[self.index(j, i)],
+        }
+    }
+    /// Return the upper-triangular part of the matrix, row by row:
+    /// `(0, 1), (0, 2), ..., (0, n-1), (1, 2), ..., (n-2, n-1)`.
+    pub fn condensed(&self) -> &[Option<u32>] {
+        &self.condensed
+    }
+    /// Write the matrix in the (square) PHYLIP format.
+    /// The names are truncated or padded to ten characters, and the distances larger than the cap
+    /// are written as `cap + 1`.
+    pub fn to_phylip(&self, names: &[&str]) -> String {
+        assert_eq!(names.len(), self.len, "The number of names differs");
+        let missing = self.max_k.map(|k| k as u64 + 1).unwrap_or(u64::MAX);
+        let mut phylip = format!("{}\n", self.len);
+        for (i, name) in names.iter().enumerate() {
+            let name: String = name.chars().take(10).collect();
+            phylip.push_str(&format!("{:<10}", name));
+            for j in 0..self.len {
+                let dist = self.get(i, j).map(|d| d as u64).unwrap_or(missing);
+                phylip.push_str(&format!(" {}", dist));
+            }
+            phylip.push('\n');
+        }
+        phylip
+    }
+}
+
+/// Compute the edit distances between all the pairs of `seqs` using all the available threads.
+/// See [`distance_matrix_with_threads`].
+pub fn distance_matrix(seqs: &[&[u8]], mode: AlignMode, max_k: Option<u32>) -> DistanceMatrix {
+    let threads = std::thread::available_parallelism().map_or(1, |n| n.get());
+    distance_matrix_with_threads(seqs, mode, max_k, threads)
+}
+
+/// Compute the edit distances between all the pairs of `seqs`, using `threads` threads.
+/// Each pair is aligned only once, with the shorter sequence as the query
+/// (thus, in the [`AlignMode::Infix`] mode, the distance is that of the containment).
+/// If `max_k` is given, the alignment is terminated as soon as the distance turns out to be larger than it,
+/// and the distance is reported as None.
+pub fn distance_matrix_with_threads(
+    seqs: &[&[u8]],
+    mode: AlignMode,
+    max_k: Option<u32>,
+    threads: usize,
+) -> DistanceMatrix {
+    let len = seqs.len();
+    let mut config = AlignConfig::new(mode, AlignTask::Distance);
+    if let Some(k) = max_k {
+        config = config.with_k(k);
+    }
+    let distance = |i: usize, j: usize| {
+        let (query, target) = match seqs[i].len() <= seqs[j].len() {
+            true => (seqs[i], seqs[j]),
+            false => (seqs[j], seqs[i]),
+        };
+        let aln = align_uncancellable(query, target, &config);
+        aln.is_found().then(|| aln.dist())
+    };
+    // Rows are taken one by one by the threads, as the lengths of the rows differ.
+    let next_row = AtomicUsize::new(0);
+    let mut rows: Vec<(usize, Vec<Option<u32>>)> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..threads.max(1))
+            .map(|_| {
+                scope.spawn(|| {
+                    let mut rows = Vec::new();
+                    loop {
+                        let i = next_row.fetch_add(1, Ordering::Relaxed);
+                        if len <= i {
+                            break rows;
+                        }
+                        rows.push((i, (i + 1..len).map(|j| distance(i, j)).collect()));
+                    }
+                })
+            })
+            .collect();
+        handles
+            .into_iter()
+            .flat_map(|h| h.join().unwrap())
+            .collect()
+    });
+    rows.sort_unstable_by_key(|&(i, _)| i);
+    let condensed = rows.into_iter().flat_map(|(_, row)| row).collect();
+    DistanceMatrix {
+        len,
+        max_k,
+        condensed,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn matrix_test() {
+        let seqs: Vec<&[u8]> = vec![b"ACGTACGT", b"ACGTTCGT", b"CGTAC", b"", b"ACGTACGTACGT"];
+        let matrix = distance_matrix_with_threads(&seqs, AlignMode::Infix, None, 3);
+        assert_eq!(matrix.condensed().len(), 10);
+        for i in 0..seqs.len() {
+            for j in 0..seqs.len() {
+                let (query, target) = match seqs[i].len() <= seqs[j].len() {
+                    true => (seqs[i], seqs[j]),
+                    false => (seqs[j], seqs[i]),
+                };
+                let expected = match i == j {
+                    true => 0,
+                    false => {
+                        crate::align(query, target, AlignMode::Infix, AlignTask::Distance).dist()
+                    }
+                };
+                assert_eq!(matrix.get(i, j), Some(expected));
+            }
+        }
+    }
+}
@@ -0,0 +1,245 @@
+//! Myers' bit-parallel algorithm for patterns fitting in a single 64-bit word.
+//!
+//! Edlib builds the pattern profile (`Peq`) each time `edlibAlign` is called. When the same short
+//! pattern is searched in many texts, as in barcode demultiplexing, it is cheaper to build the profile
+//! once and reuse it, which is what [`Profile`] does.
+
+pub(crate) const WORD_SIZE: usize = 64;
+const HIGH_BIT: u64 = 1 << (WORD_SIZE - 1);
+
+// A block of the pattern in a column of the text: the vertical deltas (positive and negative) of its cells,
+// and the score of its last cell.
+#[derive(Debug, Clone, Copy)]
+pub(crate) struct Block {
+    pv: u64,
+    mv: u64,
+    pub(crate) score: u32,
+}
+
+impl Block {
+    // A block whose scores increase by one downwards, ending with `score`.
+    pub(crate) fn ramp(score: u32) -> Self {
+        Self {
+            pv: !0,
+            mv: 0,
+            score,
+        }
+    }
+    // Advance the block by a column, given the equality bit-vector of the text character and the horizontal delta
+    // entering from above. Return the horizontal delta leaving from below.
+    pub(crate) fn advance(&mut self, eq: u64, hin: i32) -> i32 {
+        let hin_is_neg = (hin < 0) as u64;
+        let xv = eq | self.mv;
+        let eq = eq | hin_is_neg;
+        let xh = ((eq & self.pv).wrapping_add(self.pv) ^ self.pv) | eq;
+        let ph = self.mv | !(xh | self.pv);
+        let mh = self.pv & xh;
+        let hout = (ph & HIGH_BIT != 0) as i32 - (mh & HIGH_BIT != 0) as i32;
+        let ph = (ph << 1) | (0 < hin) as u64;
+        let mh = (mh << 1) | hin_is_neg;
+        self.pv = mh | !(xv | ph);
+        self.mv = ph & xv;
+        self.score = (self.score as i32 + hout) as u32;
+        hout
+    }
+    // Return the score of the `row`-th cell of the block (from zero).
+    pub(crate) fn score_at(&self, row: usize) -> u32 {
+        // Subtract the vertical deltas below the row.
+        let below = match row + 1 {
+            WORD_SIZE => 0,
+            shift => !0u64 << shift,
+        };
+        self.score + (self.mv & below).count_ones() - (self.pv & below).count_ones()
+    }
+}
+
+/// The profile of a pattern of at most [`WORD_SIZE`] characters: for each character `c`,
+/// the `i`-th bit of `peq[c]` is set if the `i`-th character of the pattern equals to `c`.
+#[derive(Debug, Clone)]
+pub(crate) struct Profile {
+    peq: Vec<u64>,
+    len: usize,
+}
+
+impl Profile {
+    /// Build the profile of the `pattern`, taking the additional `equalities` into account.
+    /// Return None if the pattern is longer than [`WORD_SIZE`].
+    pub(crate) fn new(pattern: &[u8], equalities: &[(u8, u8)]) -> Option<Self> {
+        if WORD_SIZE < pattern.len() {
+            return None;
+        }
+        let mut peq = vec![0u64; 256];
+        for (i, &c) in pattern.iter().enumerate() {
+            peq[c as usize] |= 1 << i;
+        }
+        // The equalities are merged from the profile of the pattern itself, so that they are not transitive.
+        let original = peq.clone();
+        for &(a, b) in equalities.iter().filter(|(a, b)| a != b) {
+            peq[a as usize] |= original[b as usize];
+            peq[b as usize] |= original[a as usize];
+        }
+        Some(Self {
+            peq,
+            len: pattern.len(),
+        })
+    }
+    pub(crate) fn len(&self) -> usize {
+        self.len
+    }
+    /// Return the equality bit-vector of the character `c`.
+    pub(crate) fn eq(&self, c: u8) -> u64 {
+        self.peq[c as usize]
+    }
+    /// Search the pattern in the `text` with free gaps at both ends of the text (edlib's infix mode).
+    /// Return the minimum distance and the first end position (inclusive) attaining it,
+    /// or None if the distance is larger than `max_dist`.
+    /// If the pattern is empty, the distance is zero and the end position is `-1`.
+    /// As the distance decreases by at most one per character of the text, the search stops as soon as
+    /// the rest of the text can not bring it below both the best so far and `max_dist + 1`.
+    pub(crate) fn search(&self, text: &[u8], max_dist: u32) -> Option<(u32, i64)> {
+        if self.len == 0 {
+            return Some((0, -1));
+        }
+        let last = 1u64 << (self.len - 1);
+        let (mut pv, mut mv) = (!0u64, 0u64);
+        let mut score = self.len as u32;
+        let mut best = (score, -1);
+        for (j, &c) in text.iter().enumerate() {
+            let eq = self.eq(c);
+            let xv = eq | mv;
+            let xh = ((eq & pv).wrapping_add(pv) ^ pv) | eq;
+            let ph = mv | !(xh | pv);
+            let mh = pv & xh;
+            if ph & last != 0 {
+                score += 1;
+            } else if mh & last != 0 {
+                score -= 1;
+            }
+            // The first row is zero in the infix mode, thus nothing is shifted in.
+            let (ph, mh) = (ph << 1, mh << 1);
+            pv = mh | !(xv | ph);
+            mv = ph & xv;
+            if score < best.0 {
+                best = (score, j as i64);
+            }
+            let remaining = (text.len() - j - 1) as i64;
+            if best.0.min(max_dist.saturating_add(1)) as i64 <= score as i64 - remaining {
+                break;
+            }
+        }
+        (best.0 <= max_dist).then_some(best)
+    }
+}
+
+/// Return the distances of the prefixes of the `pattern` to the suffixes of the `text`: the `i`-th element is
+/// the minimum edit distance between the first `i` characters of the pattern and a suffix of the text.
+/// It is the last column of the DP of edlib's infix mode, computed bit-parallel in blocks of [`WORD_SIZE`] characters.
+pub(crate) fn suffix_distances<P, T>(pattern: P, text: T) -> Vec<u32>
+where
+    P: IntoIterator<Item = u8>,
+    P::IntoIter: ExactSizeIterator,
+    T: IntoIterator<Item = u8>,
+{
+    let pattern = pattern.into_iter();
+    let len = pattern.len();
+    let block_count = len.div_ceil(WORD_SIZE);
+    let mut peq = vec![0u64; 256 * block_count];
+    for (i, c) in pattern.enumerate() {
+        peq[c as usize * block_count + i / WORD_SIZE] |= 1 << (i % WORD_SIZE);
+    }
+    // The first column scores the pattern positions from zero.
+    let mut blocks: Vec<_> = (0..block_count)
+        .map(|b| Block::ramp(((b + 1) * WORD_SIZE) as u32))
+        .collect();
+    for c in text {
+        let eq = &peq[c as usize * block_count..(c as usize + 1) * block_count];
+        // The first row is zero in the infix mode.
+        let mut hin = 0;
+        for (block, &eq) in blocks.iter_mut().zip(eq) {
+            hin = block.advance(eq, hin);
+        }
+    }
+    std::iter::once(0)
+        .chain((0..len).map(|i| blocks[i / WORD_SIZE].score_at(i % WORD_SIZE)))
+        .collect()
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{align, AlignMode, AlignTask};
+    #[test]
+    fn search_test() {
+        let text = b"TTGACGTACCGTAGGGTACGTTTAGACCAGTACAGTTTTACGATCGCCGCGTTAAA";
+        let patterns: [&[u8]; 5] = [
+            b"ACGT",
+            b"GACCAGTA",
+            b"CCCCCCCCC",
+            b"A",
+            b"TTAGACCAGTTCAGTTTTAGCGATCGCCGCG",
+        ];
+        for pattern in patterns {
+            let profile = Profile::new(pattern, &[]).unwrap();
+            let (dist, end) = profile.search(text, u32::MAX).unwrap();
+            let aln = align(pattern, text, AlignMode::Infix, AlignTask::Location);
+            assert_eq!(dist, aln.dist());
+            assert_eq!(end, aln.locations().unwrap().1[0] as i64);
+        }
+        let profile = Profile::new(b"NNNN", &[(b'N', b'A'), (b'N', b'C')]).unwrap();
+        assert_eq!(profile.search(b"GGACCAGG", 0), Some((0, 5)));
+        assert_eq!(profile.search(b"GGACGAGG", 1), Some((1, 5)));
+        assert_eq!(profile.search(b"GGGGGG", 3), None);
+        // `A` and `C` both equal `N`, but not each other.
+        let profile = Profile::new(b"AN", &[(b'N', b'A'), (b'N', b'C')]).unwrap();
+        assert_eq!(profile.search(b"CC", u32::MAX), Some((1, 0)));
+    }
+    #[test]
+    fn cutoff_test() {
+        // The search is cut off before the end of the text, and finds nothing.
+        let profile = Profile::new(b"ACGTACGT", &[]).unwrap();
+        assert_eq!(profile.search(&[b'T'; 1000], 3), None);
+        // A match at the very end is not skipped.
+        let text = [&[b'T'; 1000][..], b"ACGTACGT"].concat();
+        assert_eq!(profile.search(&text, 0), Some((0, 1007)));
+        // The same results as edlib for every `max_dist`.
+        let mut x = 35u64;
+        let mut random = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    b"ACGT"[(x >> 62) as usize]
+                })
+                .collect()
+        };
+        for _ in 0..20 {
+            let (pattern, text) = (random(12), random(40));
+            let profile = Profile::new(&pattern, &[]).unwrap();
+            let aln = align(&pattern, &text, AlignMode::Infix, AlignTask::Location);
+            let end = aln.locations().unwrap().1[0] as i64;
+            for max_dist in 0..12 {
+                let expected = (aln.dist() <= max_dist).then_some((aln.dist(), end));
+                assert_eq!(profile.search(&text, max_dist), expected);
+            }
+        }
+    }
+    #[test]
+    fn suffix_distances_test() {
+        let text = b"TTGACGTACCGTAGGGTACGTTTAGACCAGTACAGTTTTACGATCGCCGCGTTAAA";
+        // The pattern spans two blocks.
+        let pattern: Vec<u8> = text[30..].iter().chain(text.iter()).copied().collect();
+        let dists = suffix_distances(pattern.iter().copied(), text.iter().copied());
+        assert_eq!(dists.len(), pattern.len() + 1);
+        for (len, &dist) in dists.iter().enumerate() {
+            let aln = align(
+                &pattern[..len],
+                text,
+                AlignMode::Suffix,
+                AlignTask::Distance,
+            );
+            assert_eq!(dist, aln.dist(), "{}", len);
+        }
+        assert_eq!(suffix_distances(b"ACG".iter().copied(), None), [0, 1, 2, 3]);
+    }
+}
@@ -0,0 +1,298 @@
+//! Alignment modes built on top of edlib's prefix (`EDLIB_MODE_SHW`) mode:
+//! [`AlignMode::Suffix`] and [`AlignMode::Overlap`].
+use crate::{
+    alphabet_len, edlib_align, AlignConfig, AlignError, AlignMode, AlignTask, Alignment, Reporter,
+};
+
+fn reversed(seq: &[u8]) -> Vec<u8> {
+    seq.iter().rev().copied().collect()
+}
+
+/// Align the `query` to a suffix of the `target`, by aligning the reversed query to a prefix of the reversed target.
+pub(crate) fn align_suffix(
+    query: &[u8],
+    target: &[u8],
+    config: &AlignConfig,
+) -> Result<Alignment, AlignError> {
+    // Edlib does not run on empty sequences. The query is aligned to the empty suffix of the target
+    // if either is empty, as in the prefix mode.
+    if query.is_empty() || target.is_empty() {
+        let dist = query.len() as u32;
+        let operations = match config.task {
+            AlignTask::Alignment => vec![1; query.len()],
+            _ => Vec::new(),
+        };
+        let end = target.len() as i32 - 1;
+        return Ok(Alignment {
+            dist: config.k.is_none_or(|k| dist <= k).then_some(dist),
+            starts: vec![end + 1],
+            ends: vec![end],
+            operations,
+            query_location: Some((0, query.len() as i32 - 1)),
+            strategy: None,
+            k: None,
+            rounds: 0,
+            alphabet_len: alphabet_len(query, target),
+            task: config.task,
+            mode: AlignMode::Suffix,
+        });
+    }
+    let prefix = config.with_mode_task(AlignMode::Prefix, config.task);
+    let mut aln = edlib_align(&reversed(query), &reversed(target), &prefix)?;
+    let last = target.len() as i32 - 1;
+    let starts = aln.ends.iter().map(|&end| last - end).collect();
+    let ends = aln.starts.iter().map(|&start| last - start).collect();
+    aln.starts = starts;
+    aln.ends = ends;
+    aln.operations.reverse();
+    aln.mode = AlignMode::Suffix;
+    Ok(aln)
+}
+
+/// Align a suffix of one sequence to a prefix of the other.
+/// See [`AlignMode::Overlap`] for the definition of the best overlap.
+pub(crate) fn align_overlap(
+    query: &[u8],
+    target: &[u8],
+    config: &AlignConfig,
+) -> Result<Alignment, AlignError> {
+    let mut aln = Alignment {
+        dist: None,
+        starts: Vec::new(),
+        ends: Vec::new(),
+        operations: Vec::new(),
+        query_location: None,
+        strategy: None,
+        k: None,
+        rounds: 0,
+        alphabet_len: alphabet_len(query, target),
+        task: config.task,
+        mode: AlignMode::Overlap,
+    };
+    // Without any overlap, the alignment is not found.
+    if query.is_empty() || target.is_empty() {
+        return Ok(aln);
+    }
+    let mut search = Search {
+        config,
+        reporter: Reporter::new(config),
+        done: 0,
+        total: query.len() + target.len(),
+        rounds: 0,
+        best_score: 0,
+    };
+    let query_suffix = search.best_dovetail(query, target)?;
+    let target_suffix = search.best_dovetail(target, query)?;
+    search.reporter.report(1.0, None);
+    aln.rounds = search.rounds;
+    // (query range, target range, overlap), where the ranges are inclusive.
+    let ((qstart, qend), (tstart, tend), overlap) = match (query_suffix, target_suffix) {
+        (_, Some(o)) => ((0, o.end), (o.start, target.len() - 1), o),
+        (Some(o), None) => ((o.start, query.len() - 1), (0, o.end), o),
+        (None, None) => return Ok(aln),
+    };
+    if config.task == AlignTask::Alignment {
+        let mut global = config
+            .with_mode_task(AlignMode::Global, AlignTask::Alignment)
+            .with_k(overlap.dist);
+        global.progress = None;
+        let global = edlib_align(&query[qstart..=qend], &target[tstart..=tend], &global)?;
+        aln.rounds += global.rounds;
+        aln.operations = global.operations;
+        aln.strategy = global.strategy;
+    }
+    aln.dist = Some(overlap.dist);
+    aln.k = Some(overlap.k);
+    aln.starts = vec![tstart as i32];
+    aln.ends = vec![tend as i32];
+    aln.query_location = Some((qstart as i32, qend as i32));
+    Ok(aln)
+}
+
+// The suffix `a[start..]` aligned to the prefix `b[..=end]` within `dist` edits by the prefix pass bounded by `k`.
+#[derive(Debug, Clone, Copy)]
+struct Dovetail {
+    start: usize,
+    end: usize,
+    dist: u32,
+    k: u32,
+}
+
+// The search of the best overlap over the suffixes of both sequences.
+struct Search<'a> {
+    config: &'a AlignConfig,
+    reporter: Reporter<'a>,
+    // The number of suffixes of the previous passes, out of `total`.
+    done: usize,
+    total: usize,
+    // The number of passes of edlib so far.
+    rounds: u32,
+    best_score: i64,
+}
+
+impl Search<'_> {
+    // Find the suffix of `a` and the prefix of `b` scoring more than the best score so far, and update it.
+    // For each suffix of `a`, from the longest, the prefix of `b` at the minimal edit distance (the longest one if tied)
+    // is found by edlib's prefix mode, where `k` is bounded so that only a better overlap can be found.
+    // The search stops when even an exact match cannot score better.
+    fn best_dovetail(&mut self, a: &[u8], b: &[u8]) -> Result<Option<Dovetail>, AlignError> {
+        let mut prefix = self
+            .config
+            .with_mode_task(AlignMode::Prefix, AlignTask::Location);
+        prefix.progress = None;
+        let mut best = None;
+        let done = self.done;
+        self.done += a.len();
+        for start in 0..a.len() {
+            let len = (a.len() - start) as i64;
+            // The prefix of `b` is at most `len + dist` and `|b|` long,
+            // thus the score is at most `2 * len - 3 * dist` and `len + |b| - 4 * dist`.
+            let bound = ((2 * len - self.best_score - 1) / 3)
+                .min((len + b.len() as i64 - self.best_score - 1) / 4);
+            if bound < 0 {
+                break;
+            }
+            let k = self.config.k.map_or(bound, |k| bound.min(k as i64)) as u32;
+            self.reporter
+                .report((done + start) as f64 / self.total as f64, Some(k));
+            if self.config.is_cancelled() {
+                return Err(AlignError::Cancelled);
+            }
+            prefix.k = Some(k);
+            let aln = edlib_align(&a[start..], b, &prefix)?;
+            self.rounds += aln.rounds;
+            let (dist, end) = match (aln.dist, aln.ends.last()) {
+                (Some(dist), Some(&end)) if 0 <= end => (dist, end as usize),
+                _ => continue,
+            };
+            let score = len + end as i64 + 1 - 4 * dist as i64;
+            if self.best_score < score {
+                self.best_score = score;
+                best = Some(Dovetail {
+                    start,
+                    end,
+                    dist,
+                    k,
+                });
+            }
+        }
+        Ok(best)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use crate::{align, align_with, AlignConfig, AlignMode, AlignTask};
+    #[test]
+    fn suffix_test() {
+        let target = b"GGGGACGTTCGT";
+        let query = b"ACGATCGT";
+        let aln = align(query, target, AlignMode::Suffix, AlignTask::Alignment);
+        assert_eq!(aln.dist(), 1);
+        assert_eq!(aln.location(), Some((4, 11)));
+        assert_eq!(aln.operations(), Some([0, 0, 0, 3, 0, 0, 0, 0].as_slice()));
+    }
+    #[test]
+    fn overlap_test() {
+        // The suffix of the target overlaps with the prefix of the query, with a mismatch and a deletion.
+        let query = b"ACGTTACGGTCAAAAAAA";
+        let target = b"CCCCCCCCACGTAACGGGTC";
+        let aln = align(query, target, AlignMode::Overlap, AlignTask::Alignment);
+        assert_eq!(aln.dist(), 2);
+        assert_eq!(aln.query_location(), Some((0, 10)));
+        assert_eq!(aln.location(), Some((8, 19)));
+        let ops = aln.operations().unwrap();
+        assert_eq!(ops.iter().filter(|&&op| op != 0).count(), 2);
+        assert_eq!(ops.iter().filter(|&&op| op != 2).count(), 11);
+        assert_eq!(ops.iter().filter(|&&op| op != 1).count(), 12);
+        // The passes of the prefix mode over the suffixes, and that of the global alignment.
+        assert!(aln.k().is_some_and(|k| 2 <= k));
+        assert!(2 < aln.rounds());
+        // No overlap at all.
+        let aln = align(
+            b"AAAAAA",
+            b"CCCCCC",
+            AlignMode::Overlap,
+            AlignTask::Location,
+        );
+        assert!(!aln.is_found());
+        assert_eq!(aln.query_location(), None);
+        assert_eq!(aln.location(), None);
+        for (query, target) in [(&b""[..], &b"ACGT"[..]), (b"ACGT", b""), (b"", b"")] {
+            let aln = align(query, target, AlignMode::Overlap, AlignTask::Alignment);
+            assert!(!aln.is_found());
+        }
+        // The overlap is beyond `k`.
+        let config = AlignConfig::new(AlignMode::Overlap, AlignTask::Location);
+        assert!(align_with(query, target, &config.clone().with_k(2)).is_found());
+        assert!(!align_with(query, target, &config.with_k(1)).is_found());
+    }
+    #[test]
+    fn overlap_score_test() {
+        // The same score as aligning every suffix to the prefixes of the other sequence at the minimal distance.
+        let mut x = 11u64;
+        let mut random = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    b"ACGT"[(x >> 62) as usize]
+                })
+                .collect()
+        };
+        let score = |aln: &crate::Alignment| {
+            let ((qs, qe), (ts, te)) = (aln.query_location().unwrap(), aln.location().unwrap());
+            (qe - qs + 1 + te - ts + 1) as i64 - 4 * aln.dist() as i64
+        };
+        for _ in 0..10 {
+            let (shared, query_only, target_only) = (random(30), random(10), random(10));
+            let query = [query_only.as_slice(), &shared].concat();
+            let target = [&shared[..25], target_only.as_slice()].concat();
+            let aln = align(&query, &target, AlignMode::Overlap, AlignTask::Alignment);
+            let mut best = 0;
+            for (a, b) in [(&query, &target), (&target, &query)] {
+                for start in 0..a.len() {
+                    let suffix = &a[start..];
+                    // The longest prefix at the minimal distance, including the empty one.
+                    let (dist, end) = (0..=b.len())
+                        .map(|end| {
+                            let global =
+                                align(suffix, &b[..end], AlignMode::Global, AlignTask::Distance);
+                            (global.dist(), std::cmp::Reverse(end))
+                        })
+                        .min()
+                        .unwrap();
+                    if 0 < end.0 {
+                        best = best.max((suffix.len() + end.0) as i64 - 4 * dist as i64);
+                    }
+                }
+            }
+            assert_eq!(score(&aln), best);
+            let (qs, qe) = aln.query_location().unwrap();
+            let (ts, te) = aln.location().unwrap();
+            let global = align(
+                &query[qs..=qe],
+                &target[ts..=te],
+                AlignMode::Global,
+                AlignTask::Distance,
+            );
+            assert_eq!(global.dist(), aln.dist());
+        }
+    }
+    #[test]
+    fn empty_test() {
+        let aln = align(b"", b"ACGT", AlignMode::Suffix, AlignTask::Alignment);
+        assert_eq!((aln.dist(), aln.location()), (0, Some((4, 3))));
+        assert_eq!(aln.operations(), Some([].as_slice()));
+        // The empty suffix of an empty target ends at `-1`, as in the other modes.
+        let aln = align(b"ACG", b"", AlignMode::Suffix, AlignTask::Alignment);
+        assert_eq!(aln.dist(), 3);
+        assert_eq!(aln.location(), Some((0, usize::MAX)));
+        assert_eq!(aln.operations(), Some([1, 1, 1].as_slice()));
+        let config = AlignConfig::new(AlignMode::Suffix, AlignTask::Distance).with_k(2);
+        assert!(!align_with(b"ACG", b"", &config).is_found());
+        let aln = align(b"", b"", AlignMode::Suffix, AlignTask::Location);
+        assert_eq!((aln.dist(), aln.location()), (0, Some((0, usize::MAX))));
+    }
+}
@@ -0,0 +1,70 @@
+//! Alignment of sequences of arbitrary symbols, remapped to bytes for edlib.
+use crate::{try_align_with, AlignConfig, AlignError, Alignment};
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Align the `query` to the `target`, where the sequences consist of any symbols comparable by equality,
+/// such as words, k-mer IDs, or graph nodes.
+/// The symbols are numbered in the order of appearance and aligned as bytes, thus at most 256 distinct
+/// symbols may appear in the two sequences together; otherwise [`AlignError::TooManySymbols`] is returned.
+/// The locations and the operations refer to the positions of the symbols.
+/// The equalities of the `config` are ignored, as they are defined on bytes.
+/// # Example
+/// ```
+/// use edlib_sys::{align_symbols, AlignConfig, AlignError, AlignMode, AlignTask};
+/// let config = AlignConfig::new(AlignMode::Infix, AlignTask::Alignment);
+/// let query = ["quick", "brown", "fox"];
+/// let target = ["the", "quick", "red", "fox", "jumps"];
+/// let aln = align_symbols(&query, &target, &config).unwrap();
+/// assert_eq!(aln.dist(), 1);
+/// assert_eq!(aln.location(), Some((1, 3)));
+/// let ids: Vec<u32> = (0..300).collect();
+/// let err = align_symbols(&ids[..10], &ids, &config).unwrap_err();
+/// assert_eq!(err, AlignError::TooManySymbols(300));
+/// ```
+pub fn align_symbols<T: Eq + Hash>(
+    query: &[T],
+    target: &[T],
+    config: &AlignConfig,
+) -> Result<Alignment, AlignError> {
+    let mut codes: HashMap<&T, usize> = HashMap::new();
+    for symbol in query.iter().chain(target.iter()) {
+        let next = codes.len();
+        codes.entry(symbol).or_insert(next);
+    }
+    if 256 < codes.len() {
+        return Err(AlignError::TooManySymbols(codes.len()));
+    }
+    let encode = |seq: &[T]| -> Vec<u8> { seq.iter().map(|symbol| codes[symbol] as u8).collect() };
+    let (query, target) = (encode(query), encode(target));
+    let mut config = config.clone();
+    config.equalities.clear();
+    try_align_with(&query, &target, &config)
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AlignMode, AlignTask};
+    #[test]
+    fn symbols_test() {
+        // Symbols beyond a byte, exactly 256 of them.
+        let target: Vec<u32> = (0..256).map(|i| i * 1000).collect();
+        let mut query = target[100..200].to_vec();
+        query[50] = 7;
+        query.remove(20);
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Alignment);
+        let err = align_symbols(&query, &target, &config).unwrap_err();
+        assert_eq!(err, AlignError::TooManySymbols(257));
+        query[49] = 1000;
+        let aln = align_symbols(&query, &target, &config).unwrap();
+        assert_eq!(aln.dist(), 2);
+        assert_eq!(aln.location(), Some((100, 199)));
+        assert_eq!(aln.alphabet_len(), 256);
+        // Equalities on bytes do not apply to the symbols.
+        let config =
+            AlignConfig::new(AlignMode::Global, AlignTask::Distance).with_equalities(&[(0, 1)]);
+        let aln = align_symbols(&['x', 'y'], &['y', 'y'], &config).unwrap();
+        assert_eq!(aln.dist(), 1);
+    }
+}
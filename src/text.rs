@@ -0,0 +1,155 @@
+//! # Text
+//!
+//! Align Rust strings by characters instead of bytes, so that a multi-byte UTF-8 character
+//! counts as a single edit. With the `graphemes` feature, strings can be aligned by extended grapheme clusters
+//! as well, so that a base letter with combining marks (or an emoji sequence) is a single unit.
+//!
+//! The locations are reported both in units (characters or graphemes) and in byte offsets, as half-open ranges.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::text::{align_str, Granularity};
+//! use edlib_sys::{AlignConfig, AlignMode, AlignTask};
+//! let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location);
+//! let target = "Grüße aus Köln";
+//! let aln = align_str("Koln", target, Granularity::Chars, &config).unwrap();
+//! assert_eq!(aln.dist(), 1);
+//! assert_eq!(aln.location(), Some(10..14));
+//! let bytes = aln.byte_location().unwrap();
+//! assert_eq!(bytes, 12..17);
+//! assert_eq!(&target[bytes], "Köln");
+//! ```
+use crate::{align_symbols, AlignConfig, AlignError, Alignment};
+use std::ops::Range;
+
+/// The unit of the alignment of strings.
+/// - Chars: Unicode scalar values, i.e., `char`.
+/// - Graphemes: Extended grapheme clusters, as defined by Unicode Standard Annex #29.
+///   Requires the `graphemes` feature.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Granularity {
+    Chars,
+    #[cfg(feature = "graphemes")]
+    Graphemes,
+}
+
+/// The alignment of two strings.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct StrAlignment {
+    alignment: Alignment,
+    query_offsets: Vec<usize>,
+    target_offsets: Vec<usize>,
+}
+
+impl StrAlignment {
+    /// Return the alignment of the units. Its locations and operations refer to the units.
+    pub fn alignment(&self) -> &Alignment {
+        &self.alignment
+    }
+    /// Return the edit distance in units.
+    pub fn dist(&self) -> u32 {
+        self.alignment.dist()
+    }
+    /// Return the range of the units of the target aligned to the query.
+    pub fn location(&self) -> Option<Range<usize>> {
+        let (start, end) = self.alignment.location()?;
+        // The end is `-1 as usize` if nothing is aligned.
+        Some(start..end.wrapping_add(1))
+    }
+    /// Return the range of the units of the query aligned to the target.
+    pub fn query_location(&self) -> Option<Range<usize>> {
+        let (start, end) = self.alignment.query_location()?;
+        Some(start..end.wrapping_add(1))
+    }
+    /// Return the byte range of the target aligned to the query, which can be used to slice the target.
+    pub fn byte_location(&self) -> Option<Range<usize>> {
+        let range = self.location()?;
+        Some(self.target_offsets[range.start]..self.target_offsets[range.end])
+    }
+    /// Return the byte range of the query aligned to the target.
+    pub fn query_byte_location(&self) -> Option<Range<usize>> {
+        let range = self.query_location()?;
+        Some(self.query_offsets[range.start]..self.query_offsets[range.end])
+    }
+}
+
+// Split `text` into the units, and return them with their byte offsets followed by the length of `text`.
+fn units(text: &str, granularity: Granularity) -> (Vec<&str>, Vec<usize>) {
+    let mut offsets: Vec<usize> = match granularity {
+        Granularity::Chars => text.char_indices().map(|(i, _)| i).collect(),
+        #[cfg(feature = "graphemes")]
+        Granularity::Graphemes => {
+            use unicode_segmentation::UnicodeSegmentation;
+            text.grapheme_indices(true).map(|(i, _)| i).collect()
+        }
+    };
+    offsets.push(text.len());
+    let units = offsets.windows(2).map(|w| &text[w[0]..w[1]]).collect();
+    (units, offsets)
+}
+
+/// Align the `query` to the `target` string by the units of the `granularity`, under the `config`.
+/// Two units are equal if they are the same sequence of characters, without any normalization;
+/// the equalities of the `config` are ignored.
+/// At most 256 distinct units may appear in the two strings together, as in [`align_symbols`].
+pub fn align_str(
+    query: &str,
+    target: &str,
+    granularity: Granularity,
+    config: &AlignConfig,
+) -> Result<StrAlignment, AlignError> {
+    let (query_units, query_offsets) = units(query, granularity);
+    let (target_units, target_offsets) = units(target, granularity);
+    let alignment = align_symbols(&query_units, &target_units, config)?;
+    Ok(StrAlignment {
+        alignment,
+        query_offsets,
+        target_offsets,
+    })
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{AlignMode, AlignTask};
+    #[test]
+    fn str_test() {
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Alignment);
+        // `ü` is two bytes.
+        let aln = align_str("Müller", "Muller", Granularity::Chars, &config).unwrap();
+        assert_eq!(aln.dist(), 1);
+        let bytes = crate::align_with("Müller".as_bytes(), b"Muller", &config);
+        assert_eq!(bytes.dist(), 2);
+        assert_eq!(aln.alignment().operations().unwrap().len(), 6);
+        assert_eq!(aln.byte_location(), Some(0..6));
+        assert_eq!(aln.query_byte_location(), Some(0..7));
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Alignment);
+        let aln = align_str("東京", "大阪と東京都", Granularity::Chars, &config).unwrap();
+        assert_eq!(aln.location(), Some(3..5));
+        assert_eq!(aln.byte_location(), Some(9..15));
+        // Nothing is aligned to an empty query.
+        let aln = align_str("", "abc", Granularity::Chars, &config).unwrap();
+        assert_eq!(aln.dist(), 0);
+        assert_eq!(aln.query_byte_location(), Some(0..0));
+    }
+    #[cfg(feature = "graphemes")]
+    #[test]
+    fn grapheme_test() {
+        let config = AlignConfig::new(AlignMode::Global, AlignTask::Distance);
+        // `e` followed by a combining acute accent is a grapheme of two chars.
+        let decomposed = "cafe\u{301}";
+        let chars = align_str(decomposed, "cafe", Granularity::Chars, &config).unwrap();
+        assert_eq!(chars.dist(), 1);
+        let graphemes = align_str(decomposed, "cafe", Granularity::Graphemes, &config).unwrap();
+        assert_eq!(graphemes.dist(), 1);
+        let graphemes = align_str(decomposed, "cafa", Granularity::Graphemes, &config).unwrap();
+        assert_eq!(graphemes.dist(), 1);
+        let chars = align_str(decomposed, "cafa", Granularity::Chars, &config).unwrap();
+        assert_eq!(chars.dist(), 2);
+        let config = AlignConfig::new(AlignMode::Infix, AlignTask::Location);
+        let target = "I 👍🏽 it";
+        let aln = align_str("👍🏽", target, Granularity::Graphemes, &config).unwrap();
+        assert_eq!(aln.location(), Some(2..3));
+        assert_eq!(&target[aln.byte_location().unwrap()], "👍🏽");
+    }
+}
@@ -0,0 +1,327 @@
+//! # Adapter and primer trimming
+//!
+//! Find adapters in reads and trim them, in a similar way to cutadapt.
+//!
+//! - A 3' adapter is searched anywhere in the read by infix alignment, or, if it is not fully present,
+//!   as a prefix of the adapter hanging over the 3' end of the read. The adapter and everything after it are removed.
+//! - A 5' adapter is searched anywhere in the read, or as a suffix of the adapter hanging over the 5' end of the read.
+//!   The adapter and everything before it are removed.
+//!
+//! The number of allowed edits is given as an error rate, i.e., the edits per aligned adapter base.
+//! 5' adapters are trimmed first, then 3' adapters are searched in the rest of the read.
+//!
+//! # Example
+//! ```
+//! use edlib_sys::trim::Trimmer;
+//! let trimmer = Trimmer::new(0.1)
+//!     .with_five_prime(b"GTTCAGAGTTCTACAGTCCGACGATC")
+//!     .with_three_prime(b"TGGAATTCTCGGGTGCCAAGG");
+//! // A partial 3' adapter (with a mismatch) at the end of the read.
+//! let read = b"ACGTTTGACCAGTAGGATCCATGGAATTCTCGGGAGCC";
+//! let trimmed = trimmer.trim(read);
+//! assert_eq!(trimmed.range(), 0..21);
+//! assert_eq!(trimmed.matches().len(), 1);
+//! assert!(trimmed.matches()[0].is_partial());
+//! // The 5' adapter is partially present at the start of the read.
+//! let read = b"CGACGATCACGTTTGACCAGTAGGATCCA";
+//! assert_eq!(trimmer.trim(read).range(), 8..29);
+//! ```
+use crate::myers::suffix_distances;
+use crate::{align_uncancellable, AlignConfig, AlignMode, AlignTask};
+
+/// The end of the read where an adapter is ligated.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AdapterEnd {
+    FivePrime,
+    ThreePrime,
+}
+
+/// An adapter found in a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AdapterMatch {
+    adapter: usize,
+    location: (usize, usize),
+    dist: u32,
+    partial: bool,
+}
+
+impl AdapterMatch {
+    /// Return the index of the adapter, in the order they were added to the [`Trimmer`].
+    pub fn adapter(&self) -> usize {
+        self.adapter
+    }
+    /// Return the range of the read matching to the adapter. The end coordinate is inclusive.
+    pub fn location(&self) -> (usize, usize) {
+        self.location
+    }
+    /// Return the edit distance between the adapter (or its aligned part) and the read.
+    pub fn dist(&self) -> u32 {
+        self.dist
+    }
+    /// Return true if only a part of the adapter hangs over the end of the read.
+    pub fn is_partial(&self) -> bool {
+        self.partial
+    }
+}
+
+/// The result of trimming a read.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Trimmed {
+    range: std::ops::Range<usize>,
+    matches: Vec<AdapterMatch>,
+}
+
+impl Trimmed {
+    /// Return the range of the read to be kept.
+    pub fn range(&self) -> std::ops::Range<usize> {
+        self.range.clone()
+    }
+    /// Return the adapters found in the read, 5' one first.
+    pub fn matches(&self) -> &[AdapterMatch] {
+        &self.matches
+    }
+}
+
+/// A set of adapters to be trimmed.
+#[derive(Debug, Clone)]
+pub struct Trimmer {
+    adapters: Vec<(Vec<u8>, AdapterEnd)>,
+    error_rate: f64,
+    min_overlap: usize,
+}
+
+impl Trimmer {
+    /// Create a trimmer without adapters, allowing `error_rate` edits per aligned adapter base.
+    /// Partial adapters shorter than three bases are ignored by default.
+    pub fn new(error_rate: f64) -> Self {
+        Self {
+            adapters: Vec::new(),
+            error_rate,
+            min_overlap: 3,
+        }
+    }
+    /// Add a 5' adapter.
+    /// # Panics
+    /// Panics if the adapter is empty.
+    pub fn with_five_prime(mut self, adapter: &[u8]) -> Self {
+        assert!(!adapter.is_empty(), "the 5' adapter is empty");
+        self.adapters
+            .push((adapter.to_vec(), AdapterEnd::FivePrime));
+        self
+    }
+    /// Add a 3' adapter.
+    /// # Panics
+    /// Panics if the adapter is empty.
+    pub fn with_three_prime(mut self, adapter: &[u8]) -> Self {
+        assert!(!adapter.is_empty(), "the 3' adapter is empty");
+        self.adapters
+            .push((adapter.to_vec(), AdapterEnd::ThreePrime));
+        self
+    }
+    /// Set the minimum length of the partial adapters hanging over the end of the read.
+    /// It is at least one.
+    pub fn with_min_overlap(mut self, min_overlap: usize) -> Self {
+        self.min_overlap = min_overlap.max(1);
+        self
+    }
+    // The number of edits allowed for the aligned adapter of length `len`.
+    fn max_dist(&self, len: usize) -> u32 {
+        (self.error_rate * len as f64).floor() as u32
+    }
+    // Align the `query` to the `read` in the `mode`, within the error rate.
+    // Return the location and the distance.
+    fn find(&self, query: &[u8], read: &[u8], mode: AlignMode) -> Option<((usize, usize), u32)> {
+        let config = AlignConfig::new(mode, AlignTask::Location).with_k(self.max_dist(query.len()));
+        let aln = align_uncancellable(query, read, &config);
+        Some((aln.location()?, aln.dist()))
+    }
+    // Find the `adapter` in the `read`, either entirely or partially.
+    fn find_adapter(&self, adapter: &[u8], end: AdapterEnd, read: &[u8]) -> Option<AdapterMatch> {
+        if let Some((location, dist)) = self.find(adapter, read, AlignMode::Infix) {
+            return Some(AdapterMatch {
+                adapter: 0,
+                location,
+                dist,
+                partial: false,
+            });
+        }
+        // The longest partial adapter hanging over the end, which is aligned to at most `span` bases of the read.
+        let longest = adapter.len().min(read.len() + 1).saturating_sub(1);
+        if longest < self.min_overlap {
+            return None;
+        }
+        let span = read.len().min(longest + self.max_dist(longest) as usize);
+        // The distances of every partial adapter to the end of the read, by a single pass over the read.
+        // The 5' case is the mirror image of the 3' case.
+        let dists = match end {
+            AdapterEnd::ThreePrime => suffix_distances(
+                adapter[..longest].iter().copied(),
+                read[read.len() - span..].iter().copied(),
+            ),
+            AdapterEnd::FivePrime => suffix_distances(
+                adapter[adapter.len() - longest..].iter().rev().copied(),
+                read[..span].iter().rev().copied(),
+            ),
+        };
+        let len = (self.min_overlap..=longest)
+            .rev()
+            .find(|&len| dists[len] <= self.max_dist(len))?;
+        let (part, mode) = match end {
+            AdapterEnd::ThreePrime => (&adapter[..len], AlignMode::Suffix),
+            AdapterEnd::FivePrime => (&adapter[adapter.len() - len..], AlignMode::Prefix),
+        };
+        let (location, dist) = self.find(part, read, mode)?;
+        Some(AdapterMatch {
+            adapter: 0,
+            location,
+            dist,
+            partial: true,
+        })
+    }
+    // Find the best adapter of the `end` in the `read`, maximizing the matched bases minus the edits.
+    fn best_adapter(&self, end: AdapterEnd, read: &[u8]) -> Option<AdapterMatch> {
+        self.adapters
+            .iter()
+            .enumerate()
+            .filter(|(_, (_, e))| *e == end)
+            .filter_map(|(i, (adapter, _))| {
+                let found = self.find_adapter(adapter, end, read)?;
+                Some(AdapterMatch {
+                    adapter: i,
+                    ..found
+                })
+            })
+            .max_by_key(|m| {
+                let (start, end) = m.location;
+                let score = end.wrapping_add(1).wrapping_sub(start) as i64 - 2 * m.dist as i64;
+                (score, std::cmp::Reverse(m.adapter))
+            })
+    }
+    /// Find the adapters in the `read`, and return the range to be kept.
+    pub fn trim(&self, read: &[u8]) -> Trimmed {
+        let mut matches = Vec::new();
+        let mut start = 0;
+        if let Some(found) = self.best_adapter(AdapterEnd::FivePrime, read) {
+            start = found.location.1.wrapping_add(1);
+            matches.push(found);
+        }
+        let mut end = read.len();
+        if let Some(mut found) = self.best_adapter(AdapterEnd::ThreePrime, &read[start..]) {
+            found.location = (
+                found.location.0 + start,
+                found.location.1.wrapping_add(start),
+            );
+            end = found.location.0;
+            matches.push(found);
+        }
+        Trimmed {
+            range: start..end,
+            matches,
+        }
+    }
+    /// Trim the adapters from a FASTA/FASTQ record. The quality string is trimmed as well.
+    #[cfg(feature = "io")]
+    pub fn trim_record(&self, record: &crate::io::Record) -> (crate::io::Record, Trimmed) {
+        let trimmed = self.trim(record.seq());
+        let range = trimmed.range();
+        let seq = &record.seq()[range.clone()];
+        let record = match record.qual() {
+            Some(qual) => {
+                crate::io::Record::with_qual(record.id(), record.desc(), seq, &qual[range])
+            }
+            None => crate::io::Record::new(record.id(), record.desc(), seq),
+        };
+        (record, trimmed)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::align_with;
+    #[test]
+    fn trim_test() {
+        let adapter = b"AGATCGGAAGAGC";
+        let trimmer = Trimmer::new(0.1).with_three_prime(adapter);
+        // The full adapter with an insertion in the middle of the read.
+        let read = b"ACGTACGTTTAGATCGGTAAGAGCTTTTTT";
+        let trimmed = trimmer.trim(read);
+        assert_eq!(trimmed.range(), 0..10);
+        assert_eq!(trimmed.matches()[0].dist(), 1);
+        assert!(!trimmed.matches()[0].is_partial());
+        // The partial adapter `AG` is shorter than the minimum overlap.
+        let read = b"ACGTACGTTTTCAG";
+        assert_eq!(trimmer.trim(read).range(), 0..read.len());
+        let read = b"ACGTACGTTTTCAGA";
+        assert_eq!(trimmer.trim(read).range(), 0..12);
+        let trimmer = trimmer.with_min_overlap(4);
+        assert_eq!(trimmer.trim(read).range(), 0..read.len());
+        let read = b"ACGTACGTTTTCAGAT";
+        assert_eq!(trimmer.trim(read).range(), 0..12);
+        // No adapters.
+        assert!(Trimmer::new(0.1).trim(read).matches().is_empty());
+        // The minimum overlap is at least one base.
+        let trimmer = Trimmer::new(0.1)
+            .with_three_prime(adapter)
+            .with_min_overlap(0);
+        assert_eq!(trimmer.trim(b"ACGTACGTTTTCA").range(), 0..12);
+        assert_eq!(trimmer.trim(b"").range(), 0..0);
+        // Even the error rate of one does not align an adapter to nothing.
+        let trimmer = Trimmer::new(1.0)
+            .with_five_prime(b"ACGT")
+            .with_three_prime(b"TTTT");
+        let trimmed = trimmer.trim(b"GGGG");
+        assert!(trimmed.range().start <= trimmed.range().end);
+    }
+    #[test]
+    #[should_panic]
+    fn empty_adapter_test() {
+        Trimmer::new(0.1).with_three_prime(b"");
+    }
+    #[test]
+    fn partial_test() {
+        // The same partial adapters as aligning each of them to the end of the read.
+        let mut x = 36u64;
+        let mut random = |len: usize| -> Vec<u8> {
+            (0..len)
+                .map(|_| {
+                    x = x
+                        .wrapping_mul(6364136223846793005)
+                        .wrapping_add(1442695040888963407);
+                    b"ACGT"[(x >> 62) as usize]
+                })
+                .collect()
+        };
+        let mut partials = 0;
+        for i in 0..50 {
+            let (adapter, mut read) = (random(30), random(40));
+            let len = read.len();
+            // Half of the reads end with a part of the adapter, with a substitution.
+            if i % 2 == 0 {
+                let part = 5 + i % 20;
+                read[len - part..].copy_from_slice(&adapter[..part]);
+                read[len - 2] = b'A';
+            }
+            let trimmer = Trimmer::new(0.15).with_three_prime(&adapter);
+            let found = trimmer.find_adapter(&adapter, AdapterEnd::ThreePrime, &read);
+            let expected = (3..adapter.len()).rev().find_map(|len| {
+                let part = &adapter[..len];
+                let config = AlignConfig::new(AlignMode::Suffix, AlignTask::Location)
+                    .with_k(trimmer.max_dist(len));
+                let aln = align_with(part, &read, &config);
+                Some((aln.location()?, aln.dist()))
+            });
+            partials += found.is_some() as usize;
+            assert_eq!(found.map(|m| (m.location, m.dist)), expected);
+            // The 5' adapter on the reversed read is the mirror image.
+            let (adapter, read): (Vec<_>, Vec<_>) = (
+                adapter.iter().rev().copied().collect(),
+                read.iter().rev().copied().collect(),
+            );
+            let found = trimmer.find_adapter(&adapter, AdapterEnd::FivePrime, &read);
+            let expected = expected.map(|((start, _), dist)| ((0, len - 1 - start), dist));
+            assert_eq!(found.map(|m| (m.location, m.dist)), expected);
+        }
+        assert!(20 <= partials, "{}", partials);
+    }
+}
@@ -0,0 +1,134 @@
+//! Differential tests of `align` against the naive DP in `naive`.
+mod naive;
+use edlib_sys::edit::{apply_ops, validate};
+use edlib_sys::{align, align_with, AlignConfig, AlignMode, AlignTask};
+use proptest::prelude::*;
+
+const MODES: [AlignMode; 4] = [
+    AlignMode::Global,
+    AlignMode::Prefix,
+    AlignMode::Infix,
+    AlignMode::Suffix,
+];
+
+// A random sequence over the first `alphabet` letters.
+fn sequence(alphabet: u8, max_len: usize) -> impl Strategy<Value = Vec<u8>> {
+    prop::collection::vec((0..alphabet).prop_map(|c| b'A' + c), 0..max_len)
+}
+
+// Random pairs, with the lengths crossing the 64-bit word boundary of Myers' algorithm.
+fn random_pair() -> impl Strategy<Value = (Vec<u8>, Vec<u8>)> {
+    (1u8..=20).prop_flat_map(|alphabet| (sequence(alphabet, 150), sequence(alphabet, 300)))
+}
+
+// The target contains a mutated copy of the query.
+fn similar_pair() -> impl Strategy<Value = (Vec<u8>, Vec<u8>)> {
+    (1u8..=4)
+        .prop_flat_map(|alphabet| {
+            let edits =
+                prop::collection::vec((any::<prop::sample::Index>(), 0u8..3, 0..alphabet), 0..10);
+            (
+                sequence(alphabet, 150),
+                sequence(alphabet, 40),
+                sequence(alphabet, 40),
+                edits,
+            )
+        })
+        .prop_map(|(query, prefix, suffix, edits)| {
+            let mut copy = query.clone();
+            for (index, op, c) in edits {
+                let i = index.index(copy.len() + 1);
+                match op {
+                    0 if i < copy.len() => copy[i] = b'A' + c,
+                    1 => copy.insert(i, b'A' + c),
+                    _ if i < copy.len() && 1 < copy.len() => {
+                        copy.remove(i);
+                    }
+                    _ => {}
+                }
+            }
+            let target = [prefix, copy, suffix].concat();
+            (query, target)
+        })
+}
+
+fn check(query: &[u8], target: &[u8]) -> Result<(), TestCaseError> {
+    for mode in MODES {
+        let (dist, ends) = naive::distance(query, target, mode);
+        let aln = align(query, target, mode, AlignTask::Distance);
+        prop_assert_eq!(aln.dist(), dist, "{:?}", mode);
+        let config = AlignConfig::new(mode, AlignTask::Distance).with_k(dist);
+        prop_assert!(align_with(query, target, &config).is_found());
+        if 0 < dist {
+            let config = config.with_k(dist - 1);
+            prop_assert!(!align_with(query, target, &config).is_found());
+        }
+        let aln = align(query, target, mode, AlignTask::Location);
+        prop_assert_eq!(aln.dist(), dist);
+        let (starts, found_ends) = aln.locations().unwrap();
+        if mode != AlignMode::Suffix {
+            let found_ends: Vec<_> = found_ends.iter().map(|&e| e as i64).collect();
+            prop_assert_eq!(found_ends, ends.clone(), "{:?}", mode);
+        }
+        for (&start, &end) in starts.iter().zip(found_ends.iter()) {
+            let (start, end) = (start as i64, end as i64);
+            prop_assert!(start == 0 || !matches!(mode, AlignMode::Global | AlignMode::Prefix));
+            prop_assert!(
+                end + 1 == target.len() as i64
+                    || !matches!(mode, AlignMode::Global | AlignMode::Suffix)
+            );
+            let expected = naive::starts(query, target, end, dist);
+            prop_assert!(expected.contains(&start), "{:?} {:?}", mode, expected);
+        }
+        let aln = align(query, target, mode, AlignTask::Alignment);
+        prop_assert_eq!(aln.dist(), dist);
+        prop_assert_eq!(validate(query, target, &aln, &[]), Ok(()), "{:?}", mode);
+        let (start, end) = aln.location().unwrap();
+        prop_assert!(ends.contains(&(end as i32 as i64)));
+        prop_assert!(
+            naive::starts(query, target, end as i32 as i64, dist).contains(&(start as i64))
+        );
+        // The operations rebuild the query from the aligned part of the target.
+        let ops = aln.operations().unwrap();
+        let mut query_pos = 0;
+        let mut bases = Vec::new();
+        for &op in ops {
+            if op == 1 || op == 3 {
+                bases.push(query[query_pos]);
+            }
+            query_pos += (op != 2) as usize;
+        }
+        let aligned = &target[start..end.wrapping_add(1)];
+        prop_assert_eq!(
+            apply_ops(aligned, ops, &bases),
+            Some(query.to_vec()),
+            "{:?}",
+            mode
+        );
+        // Split the problem by Hirschberg's algorithm down to the smallest pieces.
+        let config = AlignConfig::new(mode, AlignTask::Alignment).with_memory_budget(0);
+        let aln = align_with(query, target, &config);
+        prop_assert_eq!(aln.dist(), dist);
+        prop_assert_eq!(validate(query, target, &aln, &[]), Ok(()), "{:?}", mode);
+    }
+    Ok(())
+}
+
+#[test]
+fn empty_test() {
+    for (query, target) in [(&b""[..], &b""[..]), (b"", b"ACG"), (b"ACG", b"")] {
+        check(query, target).unwrap();
+    }
+}
+
+proptest! {
+    #![proptest_config(ProptestConfig::with_cases(128))]
+    #[test]
+    fn random_test((query, target) in random_pair()) {
+        check(&query, &target)?;
+    }
+    #[test]
+    fn similar_test((query, target) in similar_pair()) {
+        check(&query, &target)?;
+    }
+}
@@ -0,0 +1,55 @@
+//! A slow but obviously correct O(nm) edit distance, used as the reference of the differential tests.
+use edlib_sys::AlignMode;
+
+// The last row of the DP matrix, i.e., the distances between the whole query and
+// the target prefixes ending at each position. If `free_start` is true, the alignment
+// may start anywhere in the target.
+fn last_row(query: &[u8], target: &[u8], free_start: bool) -> Vec<u32> {
+    let mut row: Vec<u32> = match free_start {
+        true => vec![0; target.len() + 1],
+        false => (0..=target.len() as u32).collect(),
+    };
+    for (i, &q) in query.iter().enumerate() {
+        let mut next = vec![i as u32 + 1; target.len() + 1];
+        for (j, &t) in target.iter().enumerate() {
+            let diag = row[j] + (q != t) as u32;
+            next[j + 1] = diag.min(row[j + 1] + 1).min(next[j] + 1);
+        }
+        row = next;
+    }
+    row
+}
+
+/// The edit distance in the `mode` and all the end positions (inclusive) attaining it.
+/// As in edlib, the end position is -1 if the best alignment may consume no target base.
+/// Only the modes with a fixed query, i.e., Global, Prefix, Infix, and Suffix, are supported.
+pub fn distance(query: &[u8], target: &[u8], mode: AlignMode) -> (u32, Vec<i64>) {
+    let row = match mode {
+        AlignMode::Global | AlignMode::Prefix => last_row(query, target, false),
+        AlignMode::Infix | AlignMode::Suffix => last_row(query, target, true),
+        _ => panic!("{:?} is not supported", mode),
+    };
+    match mode {
+        AlignMode::Global | AlignMode::Suffix => (row[target.len()], vec![target.len() as i64 - 1]),
+        // Edlib reports only the empty alignment at the start for an empty query.
+        _ if query.is_empty() => (0, vec![-1]),
+        _ => {
+            let dist = *row.iter().min().unwrap();
+            let ends = (0..row.len()).filter(|&j| row[j] == dist);
+            (dist, ends.map(|j| j as i64 - 1).collect())
+        }
+    }
+}
+
+/// All the start positions `start` such that `query` aligns to `target[start..=end]` with `dist` edits.
+/// The start is `end + 1` if the query may align to an empty segment ending at `end`.
+pub fn starts(query: &[u8], target: &[u8], end: i64, dist: u32) -> Vec<i64> {
+    // The distances between the query and the segments ending at `end`, by aligning the reversed sequences.
+    let query: Vec<u8> = query.iter().rev().copied().collect();
+    let target: Vec<u8> = target[..(end + 1) as usize].iter().rev().copied().collect();
+    let row = last_row(&query, &target, false);
+    (0..row.len())
+        .filter(|&len| row[len] == dist)
+        .map(|len| end + 1 - len as i64)
+        .collect()
+}